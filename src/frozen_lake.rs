@@ -1,3 +1,18 @@
+use crate::nn::NN;
+use crate::task::{Task, MultiObjectiveTask};
+use rand::Rng;
+use std::{thread, time};
+
+
+// names of the network inputs and outputs as laid out in frozen_lake.txt
+pub const INPUT_LABELS: [&str; 14] = [
+	"Left F", "Left H", "Left G",
+	"Right F", "Right H", "Right G",
+	"Up F", "Up H", "Up G",
+	"Down F", "Down H", "Down G",
+	"X diff from G", "Y diff from G",
+];
+pub const OUTPUT_LABELS: [&str; 4] = ["Up", "Down", "Left", "Right"];
 
 pub enum Direction {
 	Left,
@@ -116,4 +131,110 @@ impl FrozenLake {
 	pub fn get_down(&self) -> char {
 		self.lake[self.player_y + 1][self.player_x]
 	}
+}
+
+// evolves networks with the inputs and outputs described in
+// frozen_lake.txt
+pub struct FrozenLakeTask;
+
+impl FrozenLakeTask {
+	// shows the network playing, one move every 2 seconds
+	pub fn play(&self, nn: &NN) {
+		let mut scratch = nn.new_scratch();
+		let mut frozen_lake = FrozenLake::new();
+		frozen_lake.print_board();
+		while !frozen_lake.is_game_over() {
+			let inputs = frozen_lake_inputs(&frozen_lake);
+			let outputs = nn.evaluate(&inputs, &mut scratch).unwrap();
+			frozen_lake.move_player(frozen_lake_direction(outputs));
+			thread::sleep(time::Duration::new(2, 0));
+			frozen_lake.print_board();
+		}
+	}
+}
+
+impl Task<NN> for FrozenLakeTask {
+	// the lake has no randomness
+	fn evaluate<R: Rng + ?Sized>(&self, nn: &NN, _rng: &mut R) -> f64 {
+		let frozen_lake = frozen_lake_episode(nn);
+		let fitness: f64 = 8.0 - frozen_lake.get_x_diff_from_g().abs() - frozen_lake.get_y_diff_from_g().abs();
+		fitness.exp2() + frozen_lake.get_num_moves() as f64
+	}
+
+	// only reaching the goal scores 2^8
+	fn is_solved(&self, fitness: f64) -> bool {
+		fitness >= 256.0
+	}
+}
+
+impl MultiObjectiveTask<NN> for FrozenLakeTask {
+	// minus the distance left to the goal, 0 once it is reached, and minus
+	// the number of moves, so the front trades getting close against
+	// getting there quickly
+	fn evaluate_objectives<R: Rng + ?Sized>(&self, nn: &NN, _rng: &mut R) -> Vec<f64> {
+		let frozen_lake = frozen_lake_episode(nn);
+		let distance = frozen_lake.get_x_diff_from_g().abs() + frozen_lake.get_y_diff_from_g().abs();
+		vec![-distance, -(frozen_lake.get_num_moves() as f64)]
+	}
+}
+
+// plays the lake until it is over and returns the final state. an episode
+// is at most 50 moves and often far fewer, too short for compiling into a
+// DenseNN to pay off
+fn frozen_lake_episode(nn: &NN) -> FrozenLake {
+	let mut scratch = nn.new_scratch();
+	let mut frozen_lake = FrozenLake::new();
+	while !frozen_lake.is_game_over() {
+		let inputs = frozen_lake_inputs(&frozen_lake);
+		let outputs = nn.evaluate(&inputs, &mut scratch).unwrap();
+		frozen_lake.move_player(frozen_lake_direction(outputs));
+	}
+	frozen_lake
+}
+
+// the network inputs described in frozen_lake.txt
+fn frozen_lake_inputs(frozen_lake: &FrozenLake) -> [f64; 14] {
+	let left = frozen_lake.get_left();
+	let right = frozen_lake.get_right();
+	let up = frozen_lake.get_up();
+	let down = frozen_lake.get_down();
+
+	[
+		(left == 'F' || left == 'S') as i64 as f64, // input 1
+		(left == 'H') as i64 as f64, // input 2
+		(left == 'G') as i64 as f64, // input 3
+		(right == 'F' || right == 'S') as i64 as f64, // input 4
+		(right == 'H') as i64 as f64, // input 5
+		(right == 'G') as i64 as f64, // input 6
+		(up == 'F' || up == 'S') as i64 as f64, // input 7
+		(up == 'H') as i64 as f64, // input 8
+		(up == 'G') as i64 as f64, // input 9
+		(down == 'F' || down == 'S') as i64 as f64, // input 10
+		(down == 'H') as i64 as f64, // input 11
+		(down == 'G') as i64 as f64, // input 12
+		frozen_lake.get_x_diff_from_g(), // input 13
+		frozen_lake.get_y_diff_from_g(), // input 14
+	]
+}
+
+// picks the direction of the strongest output
+fn frozen_lake_direction(outputs: &[f64]) -> Direction {
+	let up_d = outputs[0];
+	let down_d = outputs[1];
+	let left_d = outputs[2];
+	let right_d = outputs[3];
+
+	let mut direction = Direction::Left;
+
+	if up_d >= down_d && up_d >= left_d && up_d >= right_d {
+		direction = Direction::Up;
+	} else if down_d >= up_d && down_d >= left_d && down_d >= right_d {
+		direction = Direction::Down;
+	} else if left_d >= up_d && left_d >= down_d && left_d >= right_d {
+		direction = Direction::Left;
+	} else if right_d >= up_d && right_d >= down_d && right_d >= left_d {
+		direction = Direction::Right;
+	}
+
+	direction
 }
\ No newline at end of file
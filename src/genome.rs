@@ -0,0 +1,19 @@
+use rand::Rng;
+
+// anything Population can evolve. the context holds the settings and any
+// state shared across the population, like NEAT's innovation numbers.
+// equal genomes are the same genome, the hall of fame keeps one of each
+pub trait Genome: Clone + PartialEq {
+	type Context;
+
+	fn random<R: Rng + ?Sized>(context: &mut Self::Context, rng: &mut R) -> Self;
+
+	fn mutate<R: Rng + ?Sized>(&mut self, context: &mut Self::Context, rng: &mut R);
+
+	// self is the fitter parent
+	fn crossover<R: Rng + ?Sized>(&self, other: &Self, context: &mut Self::Context, rng: &mut R) -> Self;
+
+	// how different two genomes are, used for speciation. identical
+	// genomes must be at distance 0
+	fn distance(&self, other: &Self, context: &Self::Context) -> f64;
+}
@@ -1,26 +1,88 @@
+pub mod genome;
 pub mod nn;
+pub mod nsga2;
+pub mod observer;
 pub mod population;
+pub mod rng;
+pub mod species;
+pub mod selection;
 pub mod frozen_lake;
 pub mod snake;
+pub mod stats;
+pub mod task;
+pub mod termination;
 
-use crate::population::Population;
+use crate::nn::{NN, NeatConfig, NeatContext};
+use crate::observer::Observer;
+use crate::population::{Population, PopulationConfig};
+use crate::frozen_lake::{FrozenLakeTask, INPUT_LABELS, OUTPUT_LABELS};
+use crate::task::Task;
+use std::{env, fs};
 
+const CHECKPOINT_PATH: &str = "checkpoint.json";
+const CHECKPOINT_INTERVAL: usize = 10;
+
+// saves a checkpoint every CHECKPOINT_INTERVAL generations
+struct Checkpoints;
+
+impl Observer<NN> for Checkpoints {
+	fn after_reproduction(&mut self, population: &Population<NN>) {
+		if population.get_generation().is_multiple_of(CHECKPOINT_INTERVAL) {
+			if let Err(e) = population.save_checkpoint(CHECKPOINT_PATH) {
+				println!("Could not save a checkpoint: {}", e);
+			}
+		}
+	}
+}
+
+// pass a checkpoint file to resume training from it
 fn main() {
-	let mut population = Population::new(vec![14, 4], 50);
-	let mut solved_generation = 0;
-	let mut has_solved = false;
+	let mut population = match env::args().nth(1) {
+		Some(path) => Population::load_checkpoint(&path).unwrap(),
+		None => {
+			let context = NeatContext::new(vec![14, 4], NeatConfig::default()).unwrap();
+			let config = PopulationConfig { workers: 0, ..PopulationConfig::default() };
+			Population::with_config(context, 50, config)
+		},
+	};
+	population.add_observer(Box::new(Checkpoints));
+	println!("Seed: {}", population.get_seed());
+	let task = FrozenLakeTask;
 
-	while population.get_generation() < 100 {
-		population.calc_fitness();
+	loop {
+		population.calc_fitness(&task);
 		println!("Generation {}: af = {}", population.get_generation(), population.get_average_fitness());
-		population.produce_new_gen();
-		if population.get_has_solved() && !has_solved {
-			has_solved = true;
-			solved_generation = population.get_generation();
+		if let Some(reason) = population.should_stop() {
+			println!("{}.", reason);
+			break;
+		}
+		if let Err(e) = population.produce_new_gen() {
+			println!("{}", e);
+			break;
+		}
+		if population.get_fitness_report().is_degenerate() {
+			println!("{}", population.get_fitness_report());
 		}
 	}
 
+	// from the stats, so a resumed run still counts from the start
+	let solved_generation = population.get_stats().iter()
+		.find(|stats| task.is_solved(stats.max))
+		.map_or(0, |stats| stats.generation + 1);
 	println!("Solved in {} generations.", solved_generation);
-	population.play_best_individual();
+	if let Err(e) = population.get_best_individual().save_json("best_individual.json") {
+		println!("Could not save the best individual: {}", e);
+	}
+	let dot = population.get_best_individual().to_dot_with_labels(&INPUT_LABELS, &OUTPUT_LABELS);
+	if let Err(e) = fs::write("best_individual.dot", dot) {
+		println!("Could not save the best individual's graph: {}", e);
+	}
+	if let Err(e) = fs::write("stats.csv", stats::to_csv(population.get_stats())) {
+		println!("Could not save the statistics: {}", e);
+	}
+	if let Err(e) = fs::write("stats.jsonl", stats::to_jsonl(population.get_stats())) {
+		println!("Could not save the statistics: {}", e);
+	}
+	task.play(population.get_best_individual());
 	//population.print_best_individual();
 }
\ No newline at end of file
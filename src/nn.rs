@@ -1,4 +1,22 @@
-use rand;
+use rand::Rng;
+
+mod activation;
+mod crossover;
+mod dense;
+mod dot;
+mod error;
+mod innovation;
+mod mutation;
+mod neat;
+mod serialize;
+
+pub use self::activation::Activation;
+pub use self::crossover::Crossover;
+pub use self::dense::DenseNN;
+pub use self::error::NNError;
+pub use self::innovation::InnovationTracker;
+pub use self::mutation::{MutationOperator, WeightMutation};
+pub use self::neat::{NeatConfig, NeatContext};
 
 #[derive(Copy, Clone, PartialEq)]
 enum NodeType {
@@ -8,18 +26,52 @@ enum NodeType {
 	Output,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct Node {
+	id: usize,
 	node_type: NodeType,
-	value: f64,
+	activation: Activation,
 	layer: usize,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct Connection {
+	innovation: usize,
 	input: usize,
 	output: usize,
 	weight: f64,
+	enabled: bool,
+	// reads the input node's activation from the previous step
+	recurrent: bool,
+}
+
+// order in which feed_forward visits the nodes, cached so evaluation
+// does not depend on how the connections happen to be stored
+#[derive(Clone, Default)]
+struct EvalPlan {
+	// hidden and output nodes, every node after the nodes feeding it
+	order: Vec<usize>,
+	// enabled connections going into each node
+	incoming: Vec<Vec<usize>>,
+}
+
+// buffers for NN::evaluate, so one network can be run from many threads
+// or environments at once. the recurrent state lives here as well, keep
+// one scratch per episode and reset it between episodes
+#[derive(Clone, Default)]
+pub struct Scratch {
+	values: Vec<f64>,
+	// activations from the previous step, read by recurrent connections
+	state: Vec<f64>,
+	outputs: Vec<f64>,
+}
+
+impl Scratch {
+	pub fn reset(&mut self) {
+		for state in self.state.iter_mut() {
+			*state = 0.0;
+		}
+	}
 }
 
 #[derive(Clone)]
@@ -29,19 +81,46 @@ pub struct NN {
 	num_outputs: usize,
 	num_hidden: usize,
 	num_layers: usize,
+	recurrent: bool,
 	nodes: Vec<Node>,
 	connections: Vec<Connection>,
+	plan: EvalPlan,
+	// used by feed_forward
+	scratch: Scratch,
+}
+
+// the same genes, including activations and disabled connections. the
+// plan and scratch follow from the genes and are not compared
+impl PartialEq for NN {
+	fn eq(&self, other: &NN) -> bool {
+		self.num_inputs == other.num_inputs
+			&& self.num_outputs == other.num_outputs
+			&& self.recurrent == other.recurrent
+			&& self.nodes == other.nodes
+			&& self.connections == other.connections
+	}
 }
 
 impl NN {
-	pub fn new(nn_info: Vec<usize>) -> NN {
+	pub fn new<R: Rng + ?Sized>(nn_info: Vec<usize>, rng: &mut R) -> Result<NN, NNError> {
+		let activations = vec![Activation::default(); nn_info.len().saturating_sub(1)];
+		NN::with_activations(nn_info, activations, rng)
+	}
+
+	// activations holds one entry per layer after the input layer
+	pub fn with_activations<R: Rng + ?Sized>(nn_info: Vec<usize>, activations: Vec<Activation>, rng: &mut R) -> Result<NN, NNError> {
+		if nn_info.len() < 2 {
+			return Err(NNError::TooFewLayers(nn_info.len()));
+		}
+		if let Some(layer) = nn_info.iter().position(|&size| size == 0) {
+			return Err(NNError::EmptyLayer(layer));
+		}
+		if activations.len() + 1 != nn_info.len() {
+			return Err(NNError::ActivationMismatch { expected: nn_info.len() - 1, got: activations.len() });
+		}
 		let num_inputs = nn_info[0];
 		let num_outputs = nn_info[nn_info.len() - 1];
-		let mut num_hidden = 0;
-		for i in 0..nn_info.len() {
-			num_hidden += nn_info[i];
-		}
-		num_hidden -= num_inputs - num_outputs;
+		let num_hidden = nn_info[1..nn_info.len() - 1].iter().sum();
 
 		let mut nn = NN {
 			num_bias: 1,
@@ -49,21 +128,26 @@ impl NN {
 			num_outputs: num_outputs,
 			num_hidden: num_hidden,
 			num_layers: nn_info.len(),
+			recurrent: false,
 			nodes: Vec::new(),
 			connections: Vec::new(),
+			plan: EvalPlan::default(),
+			scratch: Scratch::default(),
 		};
 
-		nn.init_nodes(nn_info);
-		nn.init_connections();
+		nn.init_nodes(nn_info, activations);
+		nn.init_connections(rng);
+		nn.build_plan()?;
 
-		nn
+		Ok(nn)
 	}
 
-	fn init_nodes(&mut self, nn_info: Vec<usize>) {
+	fn init_nodes(&mut self, nn_info: Vec<usize>, activations: Vec<Activation>) {
 		// add bias node
 		self.nodes.push(Node {
+			id: 0,
 			node_type: NodeType::Bias,
-			value: 1.0,
+			activation: Activation::Identity,
 			layer: 0,
 		});
 		// create nodes
@@ -71,20 +155,23 @@ impl NN {
 			for _j in 0..nn_info[i] {
 				if i == 0 {
 					self.nodes.push(Node {
+						id: self.nodes.len(),
 						node_type: NodeType::Input,
-						value: 0.0,
+						activation: Activation::Identity,
 						layer: i,
 					});
 				} else if i == nn_info.len() - 1 {
 					self.nodes.push(Node {
+						id: self.nodes.len(),
 						node_type: NodeType::Output,
-						value: 0.0,
+						activation: activations[i - 1],
 						layer: i,
 					});
 				} else {
 					self.nodes.push(Node {
+						id: self.nodes.len(),
 						node_type: NodeType::Hidden,
-						value: 0.0,
+						activation: activations[i - 1],
 						layer: i,
 					});
 				}
@@ -92,7 +179,7 @@ impl NN {
 		}
 	}
 
-	fn init_connections(&mut self) {
+	fn init_connections<R: Rng + ?Sized>(&mut self, rng: &mut R) {
 		// create connections
 		for i in 0..self.nodes.len() {
 			for j in 0..self.nodes.len() {
@@ -100,18 +187,24 @@ impl NN {
 					NodeType::Bias => {
 						if self.nodes[j].node_type != NodeType::Bias && self.nodes[j].node_type != NodeType::Input {
 							self.connections.push(Connection {
+								innovation: self.connections.len(),
 								input: i,
 								output: j,
-								weight: rand::random::<f64>() * 2.0 - 1.0,
+								weight: rng.gen::<f64>() * 2.0 - 1.0,
+								enabled: true,
+								recurrent: false,
 							});
 						}
 					},
 					NodeType::Input | NodeType::Hidden => {
 						if self.nodes[i].layer + 1 == self.nodes[j].layer {
 							self.connections.push(Connection {
+								innovation: self.connections.len(),
 								input: i,
 								output: j,
-								weight: rand::random::<f64>() * 2.0 - 1.0,
+								weight: rng.gen::<f64>() * 2.0 - 1.0,
+								enabled: true,
+								recurrent: false,
 							});
 						}
 					},
@@ -121,16 +214,43 @@ impl NN {
 		}
 	}
 
+	// lets mutate_add_connection create recurrent and self-loop
+	// connections, existing recurrent connections are kept either way
+	pub fn set_recurrent(&mut self, recurrent: bool) {
+		self.recurrent = recurrent;
+	}
+
+	pub fn is_recurrent(&self) -> bool {
+		self.recurrent
+	}
+
+	// forgets the activations carried over between feed_forward calls,
+	// call it before every new episode
+	pub fn reset_state(&mut self) {
+		self.scratch.reset();
+	}
+
+	pub fn new_scratch(&self) -> Scratch {
+		Scratch {
+			values: vec![0.0; self.nodes.len()],
+			state: vec![0.0; self.nodes.len()],
+			outputs: Vec::with_capacity(self.num_outputs),
+		}
+	}
+
 	pub fn print_nodes(&self) {
 		for i in 0..self.nodes.len() {
 			println!("Node {}", i);
+			println!("id: {}", self.nodes[i].id);
 			match self.nodes[i].node_type {
 				NodeType::Bias => println!("node_type: Bias"),
 				NodeType::Input => println!("node_type: Input"),
 				NodeType::Hidden => println!("node_type: Hidden"),
 				NodeType::Output => println!("node_type: Output"),
 			}
-			println!("value: {}", self.nodes[i].value);
+			println!("activation: {:?}", self.nodes[i].activation);
+			println!("value: {}", self.scratch.values.get(i).unwrap_or(&0.0));
+			println!("state: {}", self.scratch.state.get(i).unwrap_or(&0.0));
 			println!("layer: {}", self.nodes[i].layer);
 			println!("");
 		}
@@ -139,85 +259,376 @@ impl NN {
 	pub fn print_connections(&self) {
 		for i in 0..self.connections.len() {
 			println!("Connection {}", i);
+			println!("innovation: {}", self.connections[i].innovation);
 			println!("input: {}", self.connections[i].input);
 			println!("output: {}", self.connections[i].output);
 			println!("weight: {}", self.connections[i].weight);
+			println!("enabled: {}", self.connections[i].enabled);
+			println!("recurrent: {}", self.connections[i].recurrent);
 			println!("");
 		}
 	}
 
-	// this should only be used with NNs that have
-	// the exact same structures
-	pub fn crossover(&self, parent2: NN) -> NN {
+	// parents with the exact same structure use single point crossover,
+	// otherwise genes are lined up by innovation number and self is
+	// treated as the fitter parent, so its disjoint and excess genes win
+	pub fn crossover_neat<R: Rng + ?Sized>(&self, parent2: &NN, rng: &mut R) -> Result<NN, NNError> {
+		if self.num_inputs != parent2.num_inputs || self.num_outputs != parent2.num_outputs {
+			return Err(NNError::CrossoverMismatch);
+		}
+		if self.same_structure(parent2) {
+			return self.crossover_with(parent2, Crossover::SinglePoint, rng);
+		}
 		let mut baby_nn = NN {
 			num_bias: self.num_bias,
 			num_inputs: self.num_inputs,
 			num_outputs: self.num_outputs,
 			num_hidden: self.num_hidden,
 			num_layers: self.num_layers,
+			recurrent: self.recurrent,
 			nodes: self.nodes.clone(),
 			connections: self.connections.clone(),
+			plan: self.plan.clone(),
+			scratch: Scratch::default(),
 		};
 
-		let split = rand::random::<usize>() % self.connections.len();
-
 		for i in 0..self.connections.len() {
-			let mutation_chance = rand::random::<f64>();
-			if mutation_chance <= 0.01 {
-				baby_nn.connections[i].weight = rand::random::<f64>() * 2.0 - 1.0;
-			} else {
-				if i <= split {
-					baby_nn.connections[i].weight = self.connections[i].weight;
-				} else {
-					baby_nn.connections[i].weight = parent2.connections[i].weight;
+			if let Some(gene) = parent2.find_connection(self.connections[i].innovation) {
+				if rng.gen::<bool>() {
+					baby_nn.connections[i].weight = gene.weight;
 				}
 			}
 		}
-		baby_nn
+		Ok(baby_nn)
 	}
 
-	pub fn feed_forward(&mut self, inputs: Vec<f64>) -> Result<Vec<f64>, &str> {
-		// set inputs or return error
-		if inputs.len() != self.num_inputs {
-			return Err("Inputs do not match.");
+	pub fn same_structure(&self, other: &NN) -> bool {
+		self.nodes.len() == other.nodes.len()
+			&& self.connections.len() == other.connections.len()
+			&& self.nodes.iter().zip(other.nodes.iter()).all(|(a, b)| a.id == b.id)
+			&& self.connections.iter().zip(other.connections.iter()).all(|(a, b)| a.innovation == b.innovation)
+	}
+
+	// NEAT compatibility distance between two genomes:
+	// c1 * excess + c2 * disjoint + c3 * mean weight difference
+	// the gene counts are not divided by the genome size, so one structural
+	// mutation moves two genomes as far apart in a large network as in a
+	// small one
+	pub fn compatibility_distance(&self, other: &NN, c1: f64, c2: f64, c3: f64) -> f64 {
+		let mut genes1: Vec<&Connection> = self.connections.iter().collect();
+		let mut genes2: Vec<&Connection> = other.connections.iter().collect();
+		genes1.sort_by_key(|c| c.innovation);
+		genes2.sort_by_key(|c| c.innovation);
+
+		let mut i = 0;
+		let mut j = 0;
+		let mut matching = 0;
+		let mut disjoint = 0;
+		let mut weight_difference = 0.0;
+		while i < genes1.len() && j < genes2.len() {
+			if genes1[i].innovation == genes2[j].innovation {
+				matching += 1;
+				weight_difference += (genes1[i].weight - genes2[j].weight).abs();
+				i += 1;
+				j += 1;
+			} else if genes1[i].innovation < genes2[j].innovation {
+				disjoint += 1;
+				i += 1;
+			} else {
+				disjoint += 1;
+				j += 1;
+			}
+		}
+		// whatever is left lies past the end of the other genome
+		let excess = (genes1.len() - i) + (genes2.len() - j);
+
+		let mean_weight_difference = if matching > 0 {
+			weight_difference / matching as f64
 		} else {
-			for i in 0..inputs.len() {
-				self.nodes[i+1].value = inputs[i];
+			0.0
+		};
+
+		c1 * excess as f64 + c2 * disjoint as f64 + c3 * mean_weight_difference
+	}
+
+	fn find_connection(&self, innovation: usize) -> Option<&Connection> {
+		self.connections.iter().find(|c| c.innovation == innovation)
+	}
+
+	// links two nodes that are not connected yet, gives up and returns
+	// false if no free pair turns up after a few tries. connections that
+	// point back to the same or an earlier layer are only made in
+	// recurrent mode
+	pub fn mutate_add_connection<R: Rng + ?Sized>(&mut self, tracker: &mut InnovationTracker, rng: &mut R) -> bool {
+		for _attempt in 0..20 {
+			let input = rng.gen::<usize>() % self.nodes.len();
+			let output = rng.gen::<usize>() % self.nodes.len();
+
+			match self.nodes[output].node_type {
+				NodeType::Bias | NodeType::Input => continue,
+				_ => {},
+			}
+			let recurrent = self.nodes[input].layer >= self.nodes[output].layer;
+			if recurrent {
+				if !self.recurrent {
+					continue;
+				}
+				match self.nodes[input].node_type {
+					NodeType::Bias | NodeType::Input => continue,
+					_ => {},
+				}
+			}
+			if self.connections.iter().any(|c| c.input == input && c.output == output) {
+				continue;
 			}
+
+			self.connections.push(Connection {
+				innovation: tracker.connection_innovation(self.nodes[input].id, self.nodes[output].id),
+				input,
+				output,
+				weight: rng.gen::<f64>() * 2.0 - 1.0,
+				enabled: true,
+				recurrent,
+			});
+			self.build_plan().expect("forward connections only go to later layers");
+			return true;
 		}
+		false
+	}
 
-		// resets hidden and output node values to 0.0
-		for i in 0..self.nodes.len() {
-			match self.nodes[i].node_type {
-				NodeType::Hidden | NodeType::Output => self.nodes[i].value = 0.0,
+	// disables a random connection and puts a new hidden node in its place,
+	// the incoming weight is 1.0 and the outgoing one keeps the old weight.
+	// splitting a recurrent connection keeps the delay on the outgoing half
+	pub fn mutate_add_node<R: Rng + ?Sized>(&mut self, tracker: &mut InnovationTracker, rng: &mut R) -> bool {
+		// nothing may feed forward out of an output node, so recurrent
+		// connections leaving one are not split
+		let enabled: Vec<usize> = (0..self.connections.len())
+			.filter(|&i| self.connections[i].enabled)
+			.filter(|&i| self.nodes[self.connections[i].input].node_type != NodeType::Output)
+			.collect();
+		if enabled.is_empty() {
+			return false;
+		}
+
+		let split = enabled[rng.gen::<usize>() % enabled.len()];
+		self.connections[split].enabled = false;
+		let old = self.connections[split];
+
+		let mut id = tracker.split_node_id(old.innovation);
+		if self.nodes.iter().any(|n| n.id == id) {
+			// this genome already split the same gene once
+			id = tracker.new_node_id();
+		}
+
+		let node = self.nodes.len();
+		self.nodes.push(Node {
+			id,
+			node_type: NodeType::Hidden,
+			activation: Activation::default(),
+			layer: self.nodes[old.input].layer + 1,
+		});
+		self.connections.push(Connection {
+			innovation: tracker.connection_innovation(self.nodes[old.input].id, id),
+			input: old.input,
+			output: node,
+			weight: 1.0,
+			enabled: true,
+			recurrent: false,
+		});
+		self.connections.push(Connection {
+			innovation: tracker.connection_innovation(id, self.nodes[old.output].id),
+			input: node,
+			output: old.output,
+			weight: old.weight,
+			enabled: true,
+			recurrent: old.recurrent,
+		});
+		self.num_hidden += 1;
+
+		self.build_plan().expect("splitting a connection cannot close a cycle");
+		true
+	}
+
+	// gives a random hidden or output node a different activation
+	pub fn mutate_activation<R: Rng + ?Sized>(&mut self, rng: &mut R) -> bool {
+		let candidates: Vec<usize> = (0..self.nodes.len())
+			.filter(|&i| matches!(self.nodes[i].node_type, NodeType::Hidden | NodeType::Output))
+			.collect();
+		if candidates.is_empty() {
+			return false;
+		}
+
+		let node = candidates[rng.gen::<usize>() % candidates.len()];
+		let mut activation = Activation::random(rng);
+		while activation == self.nodes[node].activation {
+			activation = Activation::random(rng);
+		}
+		self.nodes[node].activation = activation;
+		true
+	}
+
+	// sorts the nodes topologically over the enabled, non-recurrent
+	// connections. a cycle is an error unless the network is in recurrent
+	// mode, where the connections closing it become recurrent instead
+	fn build_plan(&mut self) -> Result<(), NNError> {
+		let mut incoming: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+		let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+		let mut pending = vec![0; self.nodes.len()];
+		for (i, connection) in self.connections.iter().enumerate() {
+			if !connection.enabled {
+				continue;
+			}
+			incoming[connection.output].push(i);
+			if !connection.recurrent {
+				outgoing[connection.input].push(connection.output);
+				pending[connection.output] += 1;
+			}
+		}
+
+		let mut ready: Vec<usize> = (0..self.nodes.len()).rev().filter(|&i| pending[i] == 0).collect();
+		let mut visited = 0;
+		let mut order = Vec::new();
+		while let Some(node) = ready.pop() {
+			visited += 1;
+			match self.nodes[node].node_type {
+				NodeType::Hidden | NodeType::Output => order.push(node),
 				_ => {},
 			}
+			for &next in outgoing[node].iter() {
+				pending[next] -= 1;
+				if pending[next] == 0 {
+					ready.push(next);
+				}
+			}
 		}
 
-		// feed forward the neural network
-		for i in 0..self.connections.len() {
-			let input = self.connections[i].input;
-			let output = self.connections[i].output;
-			let weight = self.connections[i].weight;
-			match self.nodes[input].node_type {
-				NodeType::Bias | NodeType::Input =>
-					self.nodes[output].value += self.nodes[input].value * weight,
-				_ => self.nodes[output].value += leaky_relu(self.nodes[input].value) * weight,
+		if visited < self.nodes.len() {
+			if !self.recurrent {
+				return Err(NNError::Cycle);
 			}
+			self.break_cycles();
+			return self.build_plan();
 		}
 
-		// set and return outputs
-		let mut outputs = Vec::new();
-		for i in 0..self.nodes.len() {
-			match self.nodes[i].node_type {
-				NodeType::Output => {
-					self.nodes[i].value = leaky_relu(self.nodes[i].value);
-					outputs.push(self.nodes[i].value);
+		self.plan = EvalPlan { order, incoming };
+		self.update_layers();
+		Ok(())
+	}
+
+	// marks every connection that closes a cycle as recurrent
+	fn break_cycles(&mut self) {
+		let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+		for (i, connection) in self.connections.iter().enumerate() {
+			if connection.enabled && !connection.recurrent {
+				outgoing[connection.input].push(i);
+			}
+		}
+
+		// 0 = unvisited, 1 = on the current path, 2 = finished
+		fn visit(node: usize, outgoing: &[Vec<usize>], connections: &[Connection], state: &mut Vec<u8>, back_edges: &mut Vec<usize>) {
+			state[node] = 1;
+			for &i in outgoing[node].iter() {
+				let next = connections[i].output;
+				match state[next] {
+					0 => visit(next, outgoing, connections, state, back_edges),
+					1 => back_edges.push(i),
+					_ => {},
 				}
-				_ => {},
 			}
+			state[node] = 2;
+		}
+
+		let mut state = vec![0; self.nodes.len()];
+		let mut back_edges = Vec::new();
+		for node in 0..self.nodes.len() {
+			if state[node] == 0 {
+				visit(node, &outgoing, &self.connections, &mut state, &mut back_edges);
+			}
+		}
+
+		for i in back_edges {
+			self.connections[i].recurrent = true;
 		}
-		Ok(outputs)
+	}
+
+	// every node's layer becomes its longest path from the inputs over
+	// non-recurrent connections and all outputs share the last layer
+	fn update_layers(&mut self) {
+		for node in self.nodes.iter_mut() {
+			node.layer = match node.node_type {
+				NodeType::Bias | NodeType::Input => 0,
+				_ => 1,
+			};
+		}
+
+		for &node in self.plan.order.iter() {
+			for &i in self.plan.incoming[node].iter() {
+				let connection = self.connections[i];
+				if !connection.recurrent {
+					let layer = self.nodes[connection.input].layer + 1;
+					self.nodes[node].layer = self.nodes[node].layer.max(layer);
+				}
+			}
+		}
+
+		let last_layer = self.nodes.iter().map(|n| n.layer).max().unwrap_or(0);
+		for node in self.nodes.iter_mut() {
+			if node.node_type == NodeType::Output {
+				node.layer = last_layer;
+			}
+		}
+		self.num_layers = last_layer + 1;
+	}
+
+	pub fn feed_forward(&mut self, inputs: Vec<f64>) -> Result<Vec<f64>, NNError> {
+		let mut scratch = std::mem::take(&mut self.scratch);
+		let outputs = self.evaluate(&inputs, &mut scratch).map(|outputs| outputs.to_vec());
+		self.scratch = scratch;
+		outputs
+	}
+
+	// runs the network without changing it, everything a call writes goes
+	// into the scratch
+	pub fn evaluate<'a>(&self, inputs: &[f64], scratch: &'a mut Scratch) -> Result<&'a [f64], NNError> {
+		if inputs.len() != self.num_inputs {
+			return Err(NNError::InputMismatch { expected: self.num_inputs, got: inputs.len() });
+		}
+		if let Some(index) = inputs.iter().position(|x| !x.is_finite()) {
+			return Err(NNError::NonFiniteInput { index, value: inputs[index] });
+		}
+		if scratch.values.len() != self.nodes.len() {
+			*scratch = self.new_scratch();
+		}
+
+		// set bias and inputs
+		scratch.values[0] = 1.0;
+		scratch.values[1..=self.num_inputs].copy_from_slice(inputs);
+
+		// feed forward the neural network in plan order
+		for &node in self.plan.order.iter() {
+			let mut sum = 0.0;
+			for &i in self.plan.incoming[node].iter() {
+				let connection = &self.connections[i];
+				if connection.recurrent {
+					sum += scratch.state[connection.input] * connection.weight;
+				} else {
+					sum += scratch.values[connection.input] * connection.weight;
+				}
+			}
+			scratch.values[node] = self.nodes[node].activation.apply(sum);
+		}
+
+		// collect outputs
+		scratch.outputs.clear();
+		for (i, node) in self.nodes.iter().enumerate() {
+			if node.node_type == NodeType::Output {
+				scratch.outputs.push(scratch.values[i]);
+			}
+		}
+
+		// remember this step's activations for the recurrent connections
+		scratch.state.copy_from_slice(&scratch.values);
+		Ok(&scratch.outputs)
 	}
 }
 
@@ -232,4 +643,101 @@ pub fn leaky_relu(x: f64) -> f64 {
 	} else {
 		return x * 0.1;
 	}
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rng::SeededRng;
+	use rand::seq::SliceRandom;
+
+	fn connect(nn: &mut NN, input: usize, output: usize) {
+		nn.connections.push(Connection {
+			innovation: nn.connections.len(),
+			input,
+			output,
+			weight: 0.5,
+			enabled: true,
+			recurrent: false,
+		});
+	}
+
+	#[test]
+	fn connection_order_does_not_matter() {
+		let mut rng = SeededRng::new(9);
+		let mut nn = NN::new(vec![3, 2, 2], &mut rng).unwrap();
+		let mut tracker = InnovationTracker::new(&nn);
+		for _i in 0..10 {
+			nn.mutate_add_node(&mut tracker, &mut rng);
+			nn.mutate_add_connection(&mut tracker, &mut rng);
+			nn.mutate_activation(&mut rng);
+		}
+
+		let mut shuffled = nn.clone();
+		shuffled.connections.shuffle(&mut rng);
+		shuffled.build_plan().unwrap();
+
+		let inputs = [0.5, -1.0, 2.0];
+		let expected = nn.feed_forward(inputs.to_vec()).unwrap();
+		let outputs = shuffled.feed_forward(inputs.to_vec()).unwrap();
+		for (output, expected) in outputs.iter().zip(expected.iter()) {
+			assert!((output - expected).abs() < 1e-9, "{} != {}", output, expected);
+		}
+	}
+
+	#[test]
+	fn cycles_need_recurrent_mode() {
+		// nodes 3 and 4 are hidden, 5 is the output
+		let mut nn = NN::new(vec![2, 2, 1], &mut SeededRng::new(1)).unwrap();
+		connect(&mut nn, 5, 3);
+		assert_eq!(nn.build_plan(), Err(NNError::Cycle));
+
+		nn.set_recurrent(true);
+		nn.build_plan().unwrap();
+		let back_edge = nn.connections.last().unwrap();
+		assert!(back_edge.recurrent);
+		assert_eq!(nn.connections.iter().filter(|c| c.recurrent).count(), 1);
+	}
+
+	#[test]
+	fn recurrent_state_carries_over_until_reset() {
+		let mut nn = NN::with_activations(vec![1, 1, 1], vec![Activation::Identity; 2], &mut SeededRng::new(1)).unwrap();
+		for connection in nn.connections.iter_mut() {
+			connection.weight = 0.5;
+		}
+		// the output feeds back into the hidden node and into itself
+		nn.set_recurrent(true);
+		connect(&mut nn, 3, 2);
+		connect(&mut nn, 3, 3);
+		nn.build_plan().unwrap();
+
+		// hidden = 0.5 + 0.5 * input + 0.5 * last output
+		// output = 0.5 + 0.5 * hidden + 0.5 * last output
+		assert_eq!(nn.feed_forward(vec![1.0]).unwrap(), vec![1.0]);
+		assert_eq!(nn.feed_forward(vec![1.0]).unwrap(), vec![1.75]);
+		assert_eq!(nn.feed_forward(vec![1.0]).unwrap(), vec![2.3125]);
+
+		nn.reset_state();
+		assert_eq!(nn.feed_forward(vec![1.0]).unwrap(), vec![1.0]);
+	}
+
+	#[test]
+	fn compatibility_distance_by_hand() {
+		// both start with innovations 0, 1 and 2
+		let mut a = NN::new(vec![2, 1], &mut SeededRng::new(1)).unwrap();
+		let mut b = a.clone();
+		for (connection, shift) in b.connections.iter_mut().zip([0.3, 0.0, -0.6].iter()) {
+			connection.weight += shift;
+		}
+		let gene = |innovation| Connection { innovation, input: 1, output: 3, weight: 0.5, enabled: true, recurrent: false };
+		a.connections.extend([3, 5].iter().map(|&innovation| gene(innovation)));
+		b.connections.extend([7, 4, 6].iter().map(|&innovation| gene(innovation)));
+
+		// a: 0 1 2 3 _ 5
+		// b: 0 1 2 _ 4 _ 6 7
+		// 3, 4 and 5 are disjoint, 6 and 7 lie past the end of a
+		assert_eq!(a.compatibility_distance(&b, 1.0, 0.0, 0.0), 2.0);
+		assert_eq!(b.compatibility_distance(&a, 0.0, 1.0, 0.0), 3.0);
+		assert!((a.compatibility_distance(&b, 0.0, 0.0, 1.0) - 0.3).abs() < 1e-12);
+		assert_eq!(a.compatibility_distance(&a.clone(), 1.0, 1.0, 0.4), 0.0);
+	}
+}
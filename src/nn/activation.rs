@@ -0,0 +1,53 @@
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use super::sigmoid;
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+	Identity,
+	Sigmoid,
+	Tanh,
+	Relu,
+	// slope used for negative inputs
+	LeakyRelu(f64),
+	Step,
+	Gaussian,
+	Sin,
+}
+
+impl Activation {
+	pub fn apply(self, x: f64) -> f64 {
+		match self {
+			Activation::Identity => x,
+			Activation::Sigmoid => sigmoid(x),
+			Activation::Tanh => x.tanh(),
+			Activation::Relu => x.max(0.0),
+			Activation::LeakyRelu(slope) => if x >= 0.0 { x } else { x * slope },
+			Activation::Step => if x > 0.0 { 1.0 } else { 0.0 },
+			Activation::Gaussian => (-x * x).exp(),
+			Activation::Sin => x.sin(),
+		}
+	}
+
+	// any activation, leaky relu gets the default slope
+	pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Activation {
+		match rng.gen::<usize>() % 8 {
+			0 => Activation::Identity,
+			1 => Activation::Sigmoid,
+			2 => Activation::Tanh,
+			3 => Activation::Relu,
+			4 => Activation::LeakyRelu(0.1),
+			5 => Activation::Step,
+			6 => Activation::Gaussian,
+			_ => Activation::Sin,
+		}
+	}
+}
+
+impl Default for Activation {
+	fn default() -> Activation {
+		Activation::LeakyRelu(0.1)
+	}
+}
@@ -0,0 +1,134 @@
+use rand::Rng;
+use rand::seq::index;
+use serde::{Serialize, Deserialize};
+
+use super::{NN, NNError, Scratch};
+
+// crossover for parents with the same structure, the child takes its
+// structure and enabled genes from the first parent and mixes weights
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Crossover {
+	// one cut over the connection list, genes after it come from parent2
+	#[default]
+	SinglePoint,
+	// each weight comes from either parent with equal chance
+	Uniform,
+	// this many distinct cuts, switching parents at each one, or one cut
+	// between every pair of genes if there are fewer gaps than that
+	MultiPoint(usize),
+	// BLX-alpha, a random weight from the parents' range widened by alpha
+	// times its width on both sides
+	Blend(f64),
+	// simulated binary crossover with this distribution index, higher
+	// values keep the child closer to its parents
+	SimulatedBinary(f64),
+	// all connections into one layer come from the same parent
+	LayerAligned,
+}
+
+impl NN {
+	pub fn crossover_with<R: Rng + ?Sized>(&self, parent2: &NN, crossover: Crossover, rng: &mut R) -> Result<NN, NNError> {
+		if !self.same_structure(parent2) {
+			return Err(NNError::StructureMismatch);
+		}
+		let mut baby_nn = self.clone();
+		baby_nn.scratch = Scratch::default();
+		let genes = self.connections.len();
+
+		match crossover {
+			Crossover::SinglePoint => {
+				let split = rng.gen::<usize>() % genes.max(1);
+				for i in split + 1..genes {
+					baby_nn.connections[i].weight = parent2.connections[i].weight;
+				}
+			},
+			Crossover::Uniform => {
+				for i in 0..genes {
+					if rng.gen::<bool>() {
+						baby_nn.connections[i].weight = parent2.connections[i].weight;
+					}
+				}
+			},
+			Crossover::MultiPoint(points) => {
+				// a cut at c switches parents after gene c, so cutting after
+				// the last gene would do nothing
+				let gaps = genes.saturating_sub(1);
+				let mut cuts = index::sample(rng, gaps, points.min(gaps)).into_vec();
+				cuts.sort_unstable();
+				let mut from_parent2 = false;
+				let mut next_cut = 0;
+				for i in 0..genes {
+					while next_cut < cuts.len() && cuts[next_cut] < i {
+						from_parent2 = !from_parent2;
+						next_cut += 1;
+					}
+					if from_parent2 {
+						baby_nn.connections[i].weight = parent2.connections[i].weight;
+					}
+				}
+			},
+			Crossover::Blend(alpha) => {
+				for i in 0..genes {
+					let x1 = self.connections[i].weight;
+					let x2 = parent2.connections[i].weight;
+					let low = x1.min(x2) - alpha * (x1 - x2).abs();
+					let high = x1.max(x2) + alpha * (x1 - x2).abs();
+					baby_nn.connections[i].weight = low + rng.gen::<f64>() * (high - low);
+				}
+			},
+			Crossover::SimulatedBinary(eta) => {
+				for i in 0..genes {
+					let x1 = self.connections[i].weight;
+					let x2 = parent2.connections[i].weight;
+					let u = rng.gen::<f64>();
+					let beta = if u <= 0.5 {
+						(2.0 * u).powf(1.0 / (eta + 1.0))
+					} else {
+						(1.0 / (2.0 * (1.0 - u))).powf(1.0 / (eta + 1.0))
+					};
+					// either of the two SBX children, with equal chance
+					let sign = if rng.gen::<bool>() { 1.0 } else { -1.0 };
+					baby_nn.connections[i].weight = 0.5 * ((x1 + x2) + sign * beta * (x1 - x2));
+				}
+			},
+			Crossover::LayerAligned => {
+				let from_parent2: Vec<bool> = (0..self.num_layers).map(|_| rng.gen::<bool>()).collect();
+				for i in 0..genes {
+					if from_parent2[self.nodes[self.connections[i].output].layer] {
+						baby_nn.connections[i].weight = parent2.connections[i].weight;
+					}
+				}
+			},
+		}
+
+		Ok(baby_nn)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rng::SeededRng;
+
+	#[test]
+	fn multi_point_switches_at_every_cut() {
+		let mut rng = SeededRng::new(3);
+		let mut parent1 = NN::new(vec![6, 4], &mut rng).unwrap();
+		let mut parent2 = parent1.clone();
+		for connection in parent1.connections.iter_mut() {
+			connection.weight = 0.0;
+		}
+		for connection in parent2.connections.iter_mut() {
+			connection.weight = 1.0;
+		}
+
+		for points in 1..6 {
+			for _i in 0..20 {
+				let baby = parent1.crossover_with(&parent2, Crossover::MultiPoint(points), &mut rng).unwrap();
+				let switches = baby.connections.windows(2).filter(|pair| pair[0].weight != pair[1].weight).count();
+				assert_eq!(switches, points);
+			}
+		}
+	}
+}
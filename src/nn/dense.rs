@@ -0,0 +1,142 @@
+use super::{NN, NNError, NodeType, Activation};
+
+// a strictly layered NN flattened into one weight matrix and bias vector
+// per layer, evaluated into preallocated buffers
+#[derive(Clone)]
+pub struct DenseNN {
+	layers: Vec<DenseLayer>,
+	// values of every layer, the inputs first and the outputs last
+	buffers: Vec<Vec<f64>>,
+}
+
+#[derive(Clone)]
+struct DenseLayer {
+	// row major, one row of weights per node in this layer
+	weights: Vec<f64>,
+	biases: Vec<f64>,
+	activations: Vec<Activation>,
+}
+
+impl NN {
+	// only works when every enabled connection runs from one layer to
+	// the next, or from the bias, and none of them is recurrent
+	pub fn compile(&self) -> Result<DenseNN, NNError> {
+		// position of every node inside its layer's buffer
+		let mut layer_nodes: Vec<Vec<usize>> = vec![Vec::new(); self.num_layers];
+		let mut position = vec![0; self.nodes.len()];
+		for (i, node) in self.nodes.iter().enumerate() {
+			let last_layer = node.layer == self.num_layers - 1;
+			match node.node_type {
+				NodeType::Bias => continue,
+				// nothing reads a hidden node sharing the outputs' layer
+				NodeType::Hidden if last_layer => continue,
+				_ => {},
+			}
+			position[i] = layer_nodes[node.layer].len();
+			layer_nodes[node.layer].push(i);
+		}
+
+		let mut layers: Vec<DenseLayer> = layer_nodes.iter().skip(1).enumerate().map(|(l, nodes)| DenseLayer {
+			weights: vec![0.0; nodes.len() * layer_nodes[l].len()],
+			biases: vec![0.0; nodes.len()],
+			activations: nodes.iter().map(|&i| self.nodes[i].activation).collect(),
+		}).collect();
+
+		for connection in self.connections.iter() {
+			if !connection.enabled {
+				continue;
+			}
+			if connection.recurrent {
+				return Err(NNError::NotLayered("it has recurrent connections."));
+			}
+			let input = &self.nodes[connection.input];
+			let output = &self.nodes[connection.output];
+			if output.node_type == NodeType::Hidden && output.layer == self.num_layers - 1 {
+				continue;
+			}
+
+			let layer = &mut layers[output.layer - 1];
+			let row = position[connection.output];
+			if input.node_type == NodeType::Bias {
+				layer.biases[row] += connection.weight;
+			} else if input.layer + 1 == output.layer {
+				let columns = layer_nodes[input.layer].len();
+				layer.weights[row * columns + position[connection.input]] += connection.weight;
+			} else {
+				return Err(NNError::NotLayered("connections skip layers."));
+			}
+		}
+
+		Ok(DenseNN {
+			layers,
+			buffers: layer_nodes.iter().map(|nodes| vec![0.0; nodes.len()]).collect(),
+		})
+	}
+}
+
+impl DenseNN {
+	pub fn feed_forward(&mut self, inputs: &[f64]) -> Result<&[f64], NNError> {
+		if inputs.len() != self.buffers[0].len() {
+			return Err(NNError::InputMismatch { expected: self.buffers[0].len(), got: inputs.len() });
+		}
+		if let Some(index) = inputs.iter().position(|x| !x.is_finite()) {
+			return Err(NNError::NonFiniteInput { index, value: inputs[index] });
+		}
+		self.buffers[0].copy_from_slice(inputs);
+
+		for (l, layer) in self.layers.iter().enumerate() {
+			let (done, rest) = self.buffers.split_at_mut(l + 1);
+			let input = &done[l];
+			for (row, value) in rest[0].iter_mut().enumerate() {
+				let weights = &layer.weights[row * input.len()..(row + 1) * input.len()];
+				let mut sum = layer.biases[row];
+				for (weight, x) in weights.iter().zip(input.iter()) {
+					sum += weight * x;
+				}
+				*value = layer.activations[row].apply(sum);
+			}
+		}
+
+		Ok(&self.buffers[self.buffers.len() - 1])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::nn::InnovationTracker;
+	use crate::rng::SeededRng;
+
+	#[test]
+	fn matches_evaluate() {
+		let mut rng = SeededRng::new(3);
+		let activations = vec![Activation::Tanh, Activation::LeakyRelu(0.2), Activation::Sigmoid];
+		let mut nn = NN::with_activations(vec![3, 4, 3, 2], activations, &mut rng).unwrap();
+		// mix the activations inside the layers as well
+		for _i in 0..6 {
+			nn.mutate_activation(&mut rng);
+		}
+
+		let mut dense = nn.compile().unwrap();
+		let mut scratch = nn.new_scratch();
+		for inputs in [[0.0, 0.0, 0.0], [1.0, -2.0, 0.5], [-3.0, 0.25, 4.0]].iter() {
+			let expected = nn.evaluate(inputs, &mut scratch).unwrap().to_vec();
+			let outputs = dense.feed_forward(inputs).unwrap();
+			assert_eq!(outputs.len(), expected.len());
+			for (output, expected) in outputs.iter().zip(expected.iter()) {
+				assert!((output - expected).abs() < 1e-9, "{} != {}", output, expected);
+			}
+		}
+	}
+
+	#[test]
+	fn skipped_layers_are_not_layered() {
+		let mut rng = SeededRng::new(3);
+		let mut nn = NN::new(vec![2, 2, 1], &mut rng).unwrap();
+		let mut tracker = InnovationTracker::new(&nn);
+		// every neighbouring layer is fully connected already, so the only
+		// new links run from an input straight to the output
+		while !nn.mutate_add_connection(&mut tracker, &mut rng) {}
+		assert!(matches!(nn.compile(), Err(NNError::NotLayered(_))));
+	}
+}
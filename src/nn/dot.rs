@@ -0,0 +1,96 @@
+use std::fmt::Write;
+
+use super::{NN, NodeType};
+
+impl NN {
+	pub fn to_dot(&self) -> String {
+		self.to_dot_with_labels(&[], &[])
+	}
+
+	// labels are matched to the inputs and outputs by position, nodes
+	// without one are named after their id
+	pub fn to_dot_with_labels(&self, input_labels: &[&str], output_labels: &[&str]) -> String {
+		let mut dot = String::new();
+		writeln!(dot, "digraph NN {{").unwrap();
+		writeln!(dot, "\trankdir=LR;").unwrap();
+		writeln!(dot, "\tnode [style=filled, fontname=\"Helvetica\"];").unwrap();
+
+		// one cluster per layer so graphviz lines the layers up
+		let mut input_number = 0;
+		let mut output_number = 0;
+		let mut node_lines: Vec<Vec<String>> = vec![Vec::new(); self.num_layers];
+		for (i, node) in self.nodes.iter().enumerate() {
+			let (label, style) = match node.node_type {
+				NodeType::Bias => ("Bias".to_string(), "shape=diamond, fillcolor=\"#d9d9d9\""),
+				NodeType::Input => {
+					input_number += 1;
+					let label = match input_labels.get(input_number - 1) {
+						Some(label) => label.to_string(),
+						None => format!("Input {}", input_number),
+					};
+					(label, "shape=box, fillcolor=\"#c6dbef\"")
+				},
+				NodeType::Hidden => (format!("{}\\n{:?}", node.id, node.activation), "shape=circle, fillcolor=\"#ffffff\""),
+				NodeType::Output => {
+					output_number += 1;
+					let label = match output_labels.get(output_number - 1) {
+						Some(label) => label.to_string(),
+						None => format!("Output {}", output_number),
+					};
+					(format!("{}\\n{:?}", label, node.activation), "shape=doublecircle, fillcolor=\"#c7e9c0\"")
+				},
+			};
+			node_lines[node.layer].push(format!("\t\tn{} [label=\"{}\", {}];", i, escape(&label), style));
+		}
+
+		for (layer, lines) in node_lines.iter().enumerate() {
+			if lines.is_empty() {
+				continue;
+			}
+			writeln!(dot, "\tsubgraph cluster_layer_{} {{", layer).unwrap();
+			writeln!(dot, "\t\tlabel=\"Layer {}\";", layer).unwrap();
+			writeln!(dot, "\t\tcolor=\"#bdbdbd\";").unwrap();
+			writeln!(dot, "\t\trank=same;").unwrap();
+			for line in lines.iter() {
+				writeln!(dot, "{}", line).unwrap();
+			}
+			writeln!(dot, "\t}}").unwrap();
+		}
+
+		// positive weights are blue, negative ones red, and the heaviest
+		// connection gets the thickest line
+		let max_weight = self.connections.iter()
+			.filter(|c| c.enabled)
+			.map(|c| c.weight.abs())
+			.fold(0.0, f64::max);
+		for connection in self.connections.iter() {
+			if !connection.enabled {
+				continue;
+			}
+			let colour = if connection.weight >= 0.0 { "#2171b5" } else { "#cb181d" };
+			let width = if max_weight > 0.0 {
+				0.5 + 3.5 * connection.weight.abs() / max_weight
+			} else {
+				0.5
+			};
+			let style = if connection.recurrent { ", style=dashed, constraint=false" } else { "" };
+			writeln!(
+				dot,
+				"\tn{} -> n{} [color=\"{}\", penwidth={:.2}, tooltip=\"{:.4}\"{}];",
+				connection.input,
+				connection.output,
+				colour,
+				width,
+				connection.weight,
+				style
+			).unwrap();
+		}
+
+		writeln!(dot, "}}").unwrap();
+		dot
+	}
+}
+
+fn escape(label: &str) -> String {
+	label.replace('"', "\\\"")
+}
@@ -0,0 +1,55 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NNError {
+	// nn_info needs at least an input and an output layer
+	TooFewLayers(usize),
+	// the layer at this position of nn_info has no nodes
+	EmptyLayer(usize),
+	// with_activations needs one activation per layer after the inputs
+	ActivationMismatch { expected: usize, got: usize },
+	InputMismatch { expected: usize, got: usize },
+	NonFiniteInput { index: usize, value: f64 },
+	// parents with different numbers of inputs or outputs
+	CrossoverMismatch,
+	// crossover_with needs parents with the same nodes and connections
+	StructureMismatch,
+	// the connections form a cycle and the network is not recurrent
+	Cycle,
+	// the network is not strictly layered, so it has no dense form
+	NotLayered(&'static str),
+	InvalidGenome(String),
+	Io(String),
+}
+
+impl fmt::Display for NNError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			NNError::TooFewLayers(got) =>
+				write!(f, "A network needs at least 2 layers, got {}.", got),
+			NNError::EmptyLayer(layer) =>
+				write!(f, "Layer {} has no nodes.", layer),
+			NNError::ActivationMismatch { expected, got } =>
+				write!(f, "Expected {} activations, got {}.", expected, got),
+			NNError::InputMismatch { expected, got } =>
+				write!(f, "Inputs do not match, expected {} got {}.", expected, got),
+			NNError::NonFiniteInput { index, value } =>
+				write!(f, "Input {} is {}.", index, value),
+			NNError::CrossoverMismatch =>
+				write!(f, "Parents do not have the same inputs and outputs."),
+			NNError::StructureMismatch =>
+				write!(f, "Parents do not have the same structure."),
+			NNError::Cycle =>
+				write!(f, "Connections form a cycle."),
+			NNError::NotLayered(reason) =>
+				write!(f, "Network cannot be compiled: {}", reason),
+			NNError::InvalidGenome(reason) =>
+				write!(f, "Invalid genome: {}", reason),
+			NNError::Io(reason) =>
+				write!(f, "{}", reason),
+		}
+	}
+}
+
+impl Error for NNError {}
@@ -0,0 +1,80 @@
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap};
+
+use super::NN;
+
+// hands out innovation numbers and node ids so the same structural
+// mutation ends up with the same numbers in every genome of a population
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InnovationTracker {
+	next_innovation: usize,
+	next_node_id: usize,
+	#[serde(serialize_with = "serialize_pairs", deserialize_with = "deserialize_pairs")]
+	connections: HashMap<(usize, usize), usize>,
+	#[serde(serialize_with = "serialize_sorted")]
+	splits: HashMap<usize, usize>,
+}
+
+impl InnovationTracker {
+	// seeds the tracker with the genes a freshly built NN starts with, all
+	// networks made from the same nn_info share them
+	pub fn new(nn: &NN) -> InnovationTracker {
+		let mut tracker = InnovationTracker {
+			next_innovation: 0,
+			next_node_id: 0,
+			connections: HashMap::new(),
+			splits: HashMap::new(),
+		};
+
+		for node in nn.nodes.iter() {
+			tracker.next_node_id = tracker.next_node_id.max(node.id + 1);
+		}
+		for connection in nn.connections.iter() {
+			let key = (nn.nodes[connection.input].id, nn.nodes[connection.output].id);
+			tracker.connections.insert(key, connection.innovation);
+			tracker.next_innovation = tracker.next_innovation.max(connection.innovation + 1);
+		}
+
+		tracker
+	}
+
+	// innovation number of the connection between two node ids
+	pub fn connection_innovation(&mut self, input_id: usize, output_id: usize) -> usize {
+		let next_innovation = &mut self.next_innovation;
+		*self.connections.entry((input_id, output_id)).or_insert_with(|| {
+			*next_innovation += 1;
+			*next_innovation - 1
+		})
+	}
+
+	// id of the node created by splitting the given connection
+	pub fn split_node_id(&mut self, innovation: usize) -> usize {
+		let next_node_id = &mut self.next_node_id;
+		*self.splits.entry(innovation).or_insert_with(|| {
+			*next_node_id += 1;
+			*next_node_id - 1
+		})
+	}
+
+	pub fn new_node_id(&mut self) -> usize {
+		self.next_node_id += 1;
+		self.next_node_id - 1
+	}
+}
+
+// JSON keys have to be strings, so the connections are stored as a list
+// of ((input id, output id), innovation), sorted to keep files stable
+fn serialize_pairs<S: Serializer>(map: &HashMap<(usize, usize), usize>, serializer: S) -> Result<S::Ok, S::Error> {
+	let mut pairs: Vec<(&(usize, usize), &usize)> = map.iter().collect();
+	pairs.sort();
+	pairs.serialize(serializer)
+}
+
+fn deserialize_pairs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<(usize, usize), usize>, D::Error> {
+	let pairs: Vec<((usize, usize), usize)> = Vec::deserialize(deserializer)?;
+	Ok(pairs.into_iter().collect())
+}
+
+fn serialize_sorted<S: Serializer>(map: &HashMap<usize, usize>, serializer: S) -> Result<S::Ok, S::Error> {
+	map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
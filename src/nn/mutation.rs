@@ -0,0 +1,72 @@
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use std::f64::consts::PI;
+
+use super::NN;
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightMutation {
+	// adds normally distributed noise with this standard deviation
+	Gaussian(f64),
+	// replaces the weight with a fresh value between -range and range
+	Reset(f64),
+	// multiplies the weight by a factor between 1 - amount and 1 + amount
+	Scale(f64),
+}
+
+// a weight mutation together with how often it fires. a genome is picked
+// with genome_rate, then each of its connections with gene_rate
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MutationOperator {
+	pub mutation: WeightMutation,
+	pub genome_rate: f64,
+	pub gene_rate: f64,
+}
+
+impl MutationOperator {
+	pub fn new(mutation: WeightMutation, genome_rate: f64, gene_rate: f64) -> MutationOperator {
+		MutationOperator {
+			mutation,
+			genome_rate,
+			gene_rate,
+		}
+	}
+}
+
+impl WeightMutation {
+	pub fn apply<R: Rng + ?Sized>(self, weight: f64, rng: &mut R) -> f64 {
+		match self {
+			WeightMutation::Gaussian(sigma) => weight + gaussian(rng) * sigma,
+			WeightMutation::Reset(range) => (rng.gen::<f64>() * 2.0 - 1.0) * range,
+			WeightMutation::Scale(amount) => weight * (1.0 + (rng.gen::<f64>() * 2.0 - 1.0) * amount),
+		}
+	}
+}
+
+impl NN {
+	// runs every operator in order, so later ones see the weights the
+	// earlier ones produced. returns whether any weight changed
+	pub fn mutate_weights<R: Rng + ?Sized>(&mut self, operators: &[MutationOperator], rng: &mut R) -> bool {
+		let mut mutated = false;
+		for operator in operators.iter() {
+			if rng.gen::<f64>() >= operator.genome_rate {
+				continue;
+			}
+			for connection in self.connections.iter_mut() {
+				if rng.gen::<f64>() < operator.gene_rate {
+					connection.weight = operator.mutation.apply(connection.weight, rng);
+					mutated = true;
+				}
+			}
+		}
+		mutated
+	}
+}
+
+// standard normal sample using the Box-Muller transform
+fn gaussian<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+	let u1 = 1.0 - rng.gen::<f64>();
+	let u2 = rng.gen::<f64>();
+	(-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
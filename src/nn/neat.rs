@@ -0,0 +1,123 @@
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::genome::Genome;
+use crate::rng::SeededRng;
+use super::{NN, NNError, Activation, Crossover, InnovationTracker, MutationOperator, WeightMutation};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NeatConfig {
+	// chance per child of splitting a connection with a new hidden node
+	pub add_node_rate: f64,
+	// chance per child of linking two unconnected nodes
+	pub add_connection_rate: f64,
+	// chance per child of giving one node a different activation
+	pub activation_mutation_rate: f64,
+	// how parents with the same structure mix their weights, parents
+	// that differ always use the innovation aligned NEAT crossover
+	pub crossover: Crossover,
+	// weight mutations applied to every child, in order
+	pub weight_mutations: Vec<MutationOperator>,
+	// activation of each layer after the inputs, empty keeps the
+	// default leaky relu everywhere
+	pub activations: Vec<Activation>,
+	// lets add connection mutations create recurrent and self-loop links
+	pub allow_recurrent: bool,
+	// weights of the excess, disjoint and weight difference terms
+	pub excess_coefficient: f64,
+	pub disjoint_coefficient: f64,
+	pub weight_coefficient: f64,
+}
+
+impl Default for NeatConfig {
+	fn default() -> NeatConfig {
+		NeatConfig {
+			add_node_rate: 0.03,
+			add_connection_rate: 0.05,
+			activation_mutation_rate: 0.0,
+			crossover: Crossover::default(),
+			weight_mutations: vec![MutationOperator::new(WeightMutation::Reset(1.0), 1.0, 0.01)],
+			activations: Vec::new(),
+			allow_recurrent: false,
+			excess_coefficient: 1.0,
+			disjoint_coefficient: 1.0,
+			weight_coefficient: 0.4,
+		}
+	}
+}
+
+// everything a population of networks shares: the layer sizes new
+// networks start with, the settings and the innovation numbers
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NeatContext {
+	nn_info: Vec<usize>,
+	config: NeatConfig,
+	innovations: InnovationTracker,
+}
+
+impl NeatContext {
+	// fails if nn_info and the activations cannot make a network
+	pub fn new(nn_info: Vec<usize>, config: NeatConfig) -> Result<NeatContext, NNError> {
+		// the weights do not matter here, only whether the network can be
+		// built, so any fixed seed will do
+		let nn = NeatContext::build(&nn_info, &config, &mut SeededRng::new(0))?;
+		Ok(NeatContext {
+			innovations: InnovationTracker::new(&nn),
+			nn_info,
+			config,
+		})
+	}
+
+	pub fn get_config(&self) -> &NeatConfig {
+		&self.config
+	}
+
+	fn build<R: Rng + ?Sized>(nn_info: &[usize], config: &NeatConfig, rng: &mut R) -> Result<NN, NNError> {
+		let mut nn = if config.activations.is_empty() {
+			NN::new(nn_info.to_vec(), rng)?
+		} else {
+			NN::with_activations(nn_info.to_vec(), config.activations.clone(), rng)?
+		};
+		nn.set_recurrent(config.allow_recurrent);
+		Ok(nn)
+	}
+}
+
+impl Genome for NN {
+	type Context = NeatContext;
+
+	fn random<R: Rng + ?Sized>(context: &mut NeatContext, rng: &mut R) -> NN {
+		NeatContext::build(&context.nn_info, &context.config, rng).expect("checked when the context was made")
+	}
+
+	fn mutate<R: Rng + ?Sized>(&mut self, context: &mut NeatContext, rng: &mut R) {
+		let config = &context.config;
+		self.mutate_weights(&config.weight_mutations, rng);
+		if rng.gen::<f64>() < config.add_node_rate {
+			self.mutate_add_node(&mut context.innovations, rng);
+		}
+		if rng.gen::<f64>() < config.add_connection_rate {
+			self.mutate_add_connection(&mut context.innovations, rng);
+		}
+		if rng.gen::<f64>() < config.activation_mutation_rate {
+			self.mutate_activation(rng);
+		}
+	}
+
+	fn crossover<R: Rng + ?Sized>(&self, other: &NN, context: &mut NeatContext, rng: &mut R) -> NN {
+		if self.same_structure(other) {
+			self.crossover_with(other, context.config.crossover, rng)
+		} else {
+			self.crossover_neat(other, rng)
+		}.expect("parents come from the same population")
+	}
+
+	fn distance(&self, other: &NN, context: &NeatContext) -> f64 {
+		self.compatibility_distance(
+			other,
+			context.config.excess_coefficient,
+			context.config.disjoint_coefficient,
+			context.config.weight_coefficient,
+		)
+	}
+}
@@ -0,0 +1,538 @@
+use serde::{de, Serialize, Serializer, Deserialize, Deserializer};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fs;
+
+use super::{NN, NNError, Node, NodeType, Connection, Activation, EvalPlan, Scratch};
+
+// genome files carry a version so older files keep loading, or fail
+// loudly, when the format changes
+const FORMAT: &str = "genetic-algorithm-nn";
+const VERSION: u32 = 1;
+const MAGIC: &[u8; 4] = b"GANN";
+
+#[derive(Serialize, Deserialize)]
+struct GenomeFile {
+	format: String,
+	version: u32,
+	recurrent: bool,
+	num_inputs: usize,
+	num_outputs: usize,
+	nodes: Vec<NodeGene>,
+	connections: Vec<ConnectionGene>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NodeGene {
+	id: usize,
+	#[serde(rename = "type")]
+	node_type: String,
+	activation: Activation,
+	layer: usize,
+}
+
+// input and output are positions in the nodes list
+#[derive(Serialize, Deserialize)]
+struct ConnectionGene {
+	innovation: usize,
+	input: usize,
+	output: usize,
+	weight: f64,
+	enabled: bool,
+	recurrent: bool,
+}
+
+fn node_type_name(node_type: NodeType) -> &'static str {
+	match node_type {
+		NodeType::Bias => "bias",
+		NodeType::Input => "input",
+		NodeType::Hidden => "hidden",
+		NodeType::Output => "output",
+	}
+}
+
+fn node_type_from_name(name: &str) -> Result<NodeType, String> {
+	match name {
+		"bias" => Ok(NodeType::Bias),
+		"input" => Ok(NodeType::Input),
+		"hidden" => Ok(NodeType::Hidden),
+		"output" => Ok(NodeType::Output),
+		_ => Err(format!("Unknown node type \"{}\".", name)),
+	}
+}
+
+impl NN {
+	pub fn to_json(&self) -> String {
+		serde_json::to_string_pretty(&self.to_genome_file()).expect("genomes always serialize")
+	}
+
+	pub fn from_json(json: &str) -> Result<NN, NNError> {
+		NN::parse_json(json).map_err(NNError::InvalidGenome)
+	}
+
+	fn parse_json(json: &str) -> Result<NN, String> {
+		let file: GenomeFile = serde_json::from_str(json).map_err(|e| format!("Bad JSON: {}", e))?;
+		if file.format != FORMAT {
+			return Err(format!("Not a genome file, format is \"{}\".", file.format));
+		}
+		NN::from_genome_file(file)
+	}
+
+	// little endian: magic, version, then the same fields as the JSON
+	// format. fails when a count, id or index does not fit in 32 bits
+	pub fn to_bytes(&self) -> Result<Vec<u8>, NNError> {
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(MAGIC);
+		bytes.extend_from_slice(&VERSION.to_le_bytes());
+		bytes.push(self.recurrent as u8);
+		write_u32(&mut bytes, self.num_inputs)?;
+		write_u32(&mut bytes, self.num_outputs)?;
+
+		write_u32(&mut bytes, self.nodes.len())?;
+		for node in self.nodes.iter() {
+			write_u32(&mut bytes, node.id)?;
+			bytes.push(node.node_type as u8);
+			write_activation(&mut bytes, node.activation);
+			write_u32(&mut bytes, node.layer)?;
+		}
+
+		write_u32(&mut bytes, self.connections.len())?;
+		for connection in self.connections.iter() {
+			write_u32(&mut bytes, connection.innovation)?;
+			write_u32(&mut bytes, connection.input)?;
+			write_u32(&mut bytes, connection.output)?;
+			bytes.extend_from_slice(&connection.weight.to_le_bytes());
+			bytes.push(connection.enabled as u8 | (connection.recurrent as u8) << 1);
+		}
+
+		Ok(bytes)
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<NN, NNError> {
+		NN::parse_bytes(bytes).map_err(NNError::InvalidGenome)
+	}
+
+	fn parse_bytes(bytes: &[u8]) -> Result<NN, String> {
+		let mut reader = Reader { bytes, position: 0 };
+		if reader.take(4)? != MAGIC {
+			return Err("Not a binary genome file.".to_string());
+		}
+
+		let version = reader.read_u32()? as u32;
+		let recurrent = reader.read_u8()? != 0;
+		let num_inputs = reader.read_u32()?;
+		let num_outputs = reader.read_u32()?;
+
+		let mut nodes = Vec::new();
+		for _i in 0..reader.read_u32()? {
+			let id = reader.read_u32()?;
+			let node_type = match reader.read_u8()? {
+				0 => NodeType::Bias,
+				1 => NodeType::Input,
+				2 => NodeType::Hidden,
+				3 => NodeType::Output,
+				other => return Err(format!("Unknown node type {}.", other)),
+			};
+			nodes.push(NodeGene {
+				id,
+				node_type: node_type_name(node_type).to_string(),
+				activation: reader.read_activation()?,
+				layer: reader.read_u32()?,
+			});
+		}
+
+		let mut connections = Vec::new();
+		for _i in 0..reader.read_u32()? {
+			let innovation = reader.read_u32()?;
+			let input = reader.read_u32()?;
+			let output = reader.read_u32()?;
+			let weight = reader.read_f64()?;
+			let flags = reader.read_u8()?;
+			connections.push(ConnectionGene {
+				innovation,
+				input,
+				output,
+				weight,
+				enabled: flags & 1 != 0,
+				recurrent: flags & 2 != 0,
+			});
+		}
+
+		if reader.position != bytes.len() {
+			return Err("Trailing bytes after genome.".to_string());
+		}
+
+		NN::from_genome_file(GenomeFile {
+			format: FORMAT.to_string(),
+			version,
+			recurrent,
+			num_inputs,
+			num_outputs,
+			nodes,
+			connections,
+		})
+	}
+
+	pub fn save_json(&self, path: &str) -> Result<(), NNError> {
+		fs::write(path, self.to_json()).map_err(|e| NNError::Io(format!("Could not write {}: {}", path, e)))
+	}
+
+	pub fn save_binary(&self, path: &str) -> Result<(), NNError> {
+		fs::write(path, self.to_bytes()?).map_err(|e| NNError::Io(format!("Could not write {}: {}", path, e)))
+	}
+
+	// reads either format, telling them apart by the binary magic
+	pub fn load(path: &str) -> Result<NN, NNError> {
+		let bytes = fs::read(path).map_err(|e| NNError::Io(format!("Could not read {}: {}", path, e)))?;
+		if bytes.starts_with(MAGIC) {
+			NN::from_bytes(&bytes)
+		} else {
+			let json = String::from_utf8(bytes)
+				.map_err(|_| NNError::InvalidGenome(format!("{} is not a genome file.", path)))?;
+			NN::from_json(&json)
+		}
+	}
+
+	fn to_genome_file(&self) -> GenomeFile {
+		GenomeFile {
+			format: FORMAT.to_string(),
+			version: VERSION,
+			recurrent: self.recurrent,
+			num_inputs: self.num_inputs,
+			num_outputs: self.num_outputs,
+			nodes: self.nodes.iter().map(|node| NodeGene {
+				id: node.id,
+				node_type: node_type_name(node.node_type).to_string(),
+				activation: node.activation,
+				layer: node.layer,
+			}).collect(),
+			connections: self.connections.iter().map(|connection| ConnectionGene {
+				innovation: connection.innovation,
+				input: connection.input,
+				output: connection.output,
+				weight: connection.weight,
+				enabled: connection.enabled,
+				recurrent: connection.recurrent,
+			}).collect(),
+		}
+	}
+
+	// rebuilds a network from a file and checks it is one NN could have
+	// produced itself
+	fn from_genome_file(file: GenomeFile) -> Result<NN, String> {
+		if file.version != VERSION {
+			return Err(format!("Unsupported genome version {}, expected {}.", file.version, VERSION));
+		}
+
+		let mut nodes = Vec::new();
+		let mut ids = HashSet::new();
+		for (i, gene) in file.nodes.iter().enumerate() {
+			let node_type = node_type_from_name(&gene.node_type)?;
+			let expected = if i == 0 {
+				Some(NodeType::Bias)
+			} else if i <= file.num_inputs {
+				Some(NodeType::Input)
+			} else {
+				None
+			};
+			match expected {
+				Some(expected) if expected != node_type =>
+					return Err(format!("Node {} should be {}.", i, node_type_name(expected))),
+				None if node_type == NodeType::Bias || node_type == NodeType::Input =>
+					return Err(format!("Node {} cannot be {}.", i, gene.node_type)),
+				_ => {},
+			}
+			if !ids.insert(gene.id) {
+				return Err(format!("Node id {} is used twice.", gene.id));
+			}
+			if let Activation::LeakyRelu(slope) = gene.activation {
+				if !slope.is_finite() {
+					return Err(format!("Node {} has a non-finite leaky relu slope.", i));
+				}
+			}
+			nodes.push(Node {
+				id: gene.id,
+				node_type,
+				activation: gene.activation,
+				layer: gene.layer,
+			});
+		}
+
+		let num_outputs = nodes.iter().filter(|n| n.node_type == NodeType::Output).count();
+		if nodes.len() <= file.num_inputs || num_outputs != file.num_outputs || num_outputs == 0 {
+			return Err(format!(
+				"Expected 1 bias, {} input and {} output nodes.",
+				file.num_inputs,
+				file.num_outputs
+			));
+		}
+
+		let mut connections = Vec::new();
+		let mut innovations = HashSet::new();
+		let mut links = HashSet::new();
+		for (i, gene) in file.connections.iter().enumerate() {
+			if gene.input >= nodes.len() || gene.output >= nodes.len() {
+				return Err(format!("Connection {} points at a missing node.", i));
+			}
+			match nodes[gene.output].node_type {
+				NodeType::Bias | NodeType::Input => return Err(format!("Connection {} feeds into an input.", i)),
+				_ => {},
+			}
+			if !gene.weight.is_finite() {
+				return Err(format!("Connection {} has a non-finite weight.", i));
+			}
+			if gene.recurrent && !file.recurrent {
+				return Err(format!("Connection {} is recurrent but the network is not.", i));
+			}
+			if !innovations.insert(gene.innovation) {
+				return Err(format!("Innovation {} is used twice.", gene.innovation));
+			}
+			if !links.insert((gene.input, gene.output)) {
+				return Err(format!("Connection {} duplicates another connection.", i));
+			}
+			connections.push(Connection {
+				innovation: gene.innovation,
+				input: gene.input,
+				output: gene.output,
+				weight: gene.weight,
+				enabled: gene.enabled,
+				recurrent: gene.recurrent,
+			});
+		}
+
+		let mut nn = NN {
+			num_bias: 1,
+			num_inputs: file.num_inputs,
+			num_outputs: file.num_outputs,
+			num_hidden: nodes.iter().filter(|n| n.node_type == NodeType::Hidden).count(),
+			num_layers: 0,
+			recurrent: file.recurrent,
+			nodes,
+			connections,
+			plan: EvalPlan::default(),
+			scratch: Scratch::default(),
+		};
+		nn.build_plan().map_err(|e| e.to_string())?;
+
+		for (i, gene) in file.nodes.iter().enumerate() {
+			if nn.nodes[i].layer != gene.layer {
+				return Err(format!("Node {} is in layer {} but its connections put it in layer {}.", i, gene.layer, nn.nodes[i].layer));
+			}
+		}
+
+		Ok(nn)
+	}
+}
+
+// networks inside other serde structures, like checkpoints, are stored
+// the same way as in a JSON genome file
+impl Serialize for NN {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.to_genome_file().serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for NN {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<NN, D::Error> {
+		let file = GenomeFile::deserialize(deserializer)?;
+		if file.format != FORMAT {
+			return Err(de::Error::custom(format!("Not a genome, format is \"{}\".", file.format)));
+		}
+		NN::from_genome_file(file).map_err(de::Error::custom)
+	}
+}
+
+// the binary format stores every count, id and index in 32 bits
+fn write_u32(bytes: &mut Vec<u8>, value: usize) -> Result<(), NNError> {
+	let value = u32::try_from(value)
+		.map_err(|_| NNError::InvalidGenome(format!("{} does not fit in the binary format's 32 bits.", value)))?;
+	bytes.extend_from_slice(&value.to_le_bytes());
+	Ok(())
+}
+
+fn write_activation(bytes: &mut Vec<u8>, activation: Activation) {
+	match activation {
+		Activation::Identity => bytes.push(0),
+		Activation::Sigmoid => bytes.push(1),
+		Activation::Tanh => bytes.push(2),
+		Activation::Relu => bytes.push(3),
+		Activation::LeakyRelu(slope) => {
+			bytes.push(4);
+			bytes.extend_from_slice(&slope.to_le_bytes());
+		},
+		Activation::Step => bytes.push(5),
+		Activation::Gaussian => bytes.push(6),
+		Activation::Sin => bytes.push(7),
+	}
+}
+
+struct Reader<'a> {
+	bytes: &'a [u8],
+	position: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+		if self.position + count > self.bytes.len() {
+			return Err("Genome ends early.".to_string());
+		}
+		let slice = &self.bytes[self.position..self.position + count];
+		self.position += count;
+		Ok(slice)
+	}
+
+	fn read_u8(&mut self) -> Result<u8, String> {
+		Ok(self.take(1)?[0])
+	}
+
+	fn read_u32(&mut self) -> Result<usize, String> {
+		let mut buffer = [0; 4];
+		buffer.copy_from_slice(self.take(4)?);
+		Ok(u32::from_le_bytes(buffer) as usize)
+	}
+
+	fn read_f64(&mut self) -> Result<f64, String> {
+		let mut buffer = [0; 8];
+		buffer.copy_from_slice(self.take(8)?);
+		Ok(f64::from_le_bytes(buffer))
+	}
+
+	fn read_activation(&mut self) -> Result<Activation, String> {
+		match self.read_u8()? {
+			0 => Ok(Activation::Identity),
+			1 => Ok(Activation::Sigmoid),
+			2 => Ok(Activation::Tanh),
+			3 => Ok(Activation::Relu),
+			4 => Ok(Activation::LeakyRelu(self.read_f64()?)),
+			5 => Ok(Activation::Step),
+			6 => Ok(Activation::Gaussian),
+			7 => Ok(Activation::Sin),
+			other => Err(format!("Unknown activation {}.", other)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::genome::Genome;
+	use crate::nn::{NeatConfig, NeatContext};
+	use crate::rng::SeededRng;
+
+	// a network with hidden nodes, disabled genes and mixed activations
+	fn evolved_nn() -> NN {
+		let config = NeatConfig {
+			add_node_rate: 0.5,
+			add_connection_rate: 0.5,
+			activation_mutation_rate: 0.5,
+			..NeatConfig::default()
+		};
+		let mut context = NeatContext::new(vec![3, 2], config).unwrap();
+		let mut rng = SeededRng::new(5);
+		let mut nn = NN::random(&mut context, &mut rng);
+		for _i in 0..20 {
+			nn.mutate(&mut context, &mut rng);
+		}
+		assert!(nn.nodes.iter().any(|node| node.node_type == NodeType::Hidden));
+		nn
+	}
+
+	fn temp_path(name: &str) -> String {
+		let path = std::env::temp_dir().join(format!("genome-test-{}-{}", name, std::process::id()));
+		path.to_str().unwrap().to_string()
+	}
+
+	#[test]
+	fn json_round_trip() {
+		let nn = evolved_nn();
+		assert!(NN::from_json(&nn.to_json()).unwrap() == nn);
+
+		let path = temp_path("json");
+		nn.save_json(&path).unwrap();
+		let loaded = NN::load(&path);
+		fs::remove_file(&path).unwrap();
+		assert!(loaded.unwrap() == nn);
+	}
+
+	#[test]
+	fn binary_round_trip() {
+		let nn = evolved_nn();
+		assert!(NN::from_bytes(&nn.to_bytes().unwrap()).unwrap() == nn);
+
+		let path = temp_path("binary");
+		nn.save_binary(&path).unwrap();
+		let loaded = NN::load(&path);
+		fs::remove_file(&path).unwrap();
+		assert!(loaded.unwrap() == nn);
+	}
+
+	// why from_json refuses a copy of a small network with one change
+	fn rejection<F: FnOnce(&mut serde_json::Value)>(change: F) -> String {
+		let nn = NN::new(vec![2, 2, 1], &mut SeededRng::new(1)).unwrap();
+		let mut file: serde_json::Value = serde_json::from_str(&nn.to_json()).unwrap();
+		change(&mut file);
+		match NN::from_json(&file.to_string()) {
+			Err(NNError::InvalidGenome(reason)) => reason,
+			Err(e) => panic!("expected an invalid genome, got {}", e),
+			Ok(_) => panic!("the changed genome loaded"),
+		}
+	}
+
+	#[test]
+	fn duplicate_innovation_is_rejected() {
+		let reason = rejection(|file| file["connections"][1]["innovation"] = file["connections"][0]["innovation"].clone());
+		assert!(reason.contains("is used twice"), "{}", reason);
+	}
+
+	#[test]
+	fn missing_node_is_rejected() {
+		let reason = rejection(|file| file["connections"][0]["output"] = serde_json::json!(99));
+		assert!(reason.contains("missing node"), "{}", reason);
+	}
+
+	#[test]
+	fn wrong_version_is_rejected() {
+		let reason = rejection(|file| file["version"] = serde_json::json!(VERSION + 1));
+		assert!(reason.contains("Unsupported genome version"), "{}", reason);
+	}
+
+	#[test]
+	fn cycle_in_non_recurrent_file_is_rejected() {
+		// the output feeds back into the first hidden node
+		let reason = rejection(|file| file["connections"].as_array_mut().unwrap().push(serde_json::json!({
+			"innovation": 100,
+			"input": 5,
+			"output": 3,
+			"weight": 0.5,
+			"enabled": true,
+			"recurrent": false,
+		})));
+		assert_eq!(reason, NNError::Cycle.to_string());
+	}
+
+	#[test]
+	fn trailing_bytes_are_rejected() {
+		let mut bytes = evolved_nn().to_bytes().unwrap();
+		bytes.push(0);
+		assert!(matches!(NN::from_bytes(&bytes), Err(NNError::InvalidGenome(reason)) if reason.contains("Trailing bytes")));
+	}
+
+	#[test]
+	fn binary_fails_on_ids_past_32_bits() {
+		let mut nn = NN::new(vec![2, 1], &mut SeededRng::new(1)).unwrap();
+		nn.nodes[3].id = u32::MAX as usize + 1;
+		assert!(matches!(nn.to_bytes(), Err(NNError::InvalidGenome(_))));
+	}
+
+	#[test]
+	fn huge_input_count_is_rejected() {
+		let nn = NN::new(vec![2, 1], &mut SeededRng::new(1)).unwrap();
+		let mut file: serde_json::Value = serde_json::from_str(&nn.to_json()).unwrap();
+		file["num_inputs"] = serde_json::json!(u64::MAX);
+		file["num_outputs"] = serde_json::json!(0);
+		for node in file["nodes"].as_array_mut().unwrap().iter_mut().skip(1) {
+			node["type"] = serde_json::json!("input");
+		}
+		assert!(NN::from_json(&file.to_string()).is_err());
+	}
+}
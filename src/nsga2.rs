@@ -0,0 +1,289 @@
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use std::cmp::Ordering;
+use std::mem;
+
+use crate::genome::Genome;
+use crate::population::evaluate_genomes;
+use crate::rng::SeededRng;
+use crate::task::MultiObjectiveTask;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Nsga2Config {
+	// threads evaluating objectives, like PopulationConfig::workers
+	pub workers: usize,
+	// seed of every random choice in the run, None picks one at random
+	pub seed: Option<u64>,
+}
+
+impl Default for Nsga2Config {
+	fn default() -> Nsga2Config {
+		Nsga2Config {
+			workers: 1,
+			seed: None,
+		}
+	}
+}
+
+// evolves genomes on several objectives with NSGA-II. each generation the
+// children are evaluated, pooled with their parents, and the best pop_size
+// of the pool by non-domination rank and then crowding distance survive
+pub struct Nsga2<G: Genome> {
+	config: Nsga2Config,
+	context: G::Context,
+	// the survivors, with their objectives, front and crowding distance
+	parents: Vec<G>,
+	objectives: Vec<Vec<f64>>,
+	ranks: Vec<usize>,
+	crowding: Vec<f64>,
+	// waiting to be evaluated by calc_fitness
+	offspring: Vec<G>,
+	pop_size: usize,
+	generation: usize,
+	seed: u64,
+	rng: SeededRng,
+}
+
+impl<G: Genome> Nsga2<G> {
+	pub fn new(context: G::Context, pop_size: usize) -> Nsga2<G> {
+		Nsga2::with_config(context, pop_size, Nsga2Config::default())
+	}
+
+	pub fn with_config(mut context: G::Context, pop_size: usize, config: Nsga2Config) -> Nsga2<G> {
+		let seed = config.seed.unwrap_or_else(rand::random);
+		let mut rng = SeededRng::new(seed);
+		let offspring = (0..pop_size).map(|_| G::random(&mut context, &mut rng)).collect();
+		Nsga2 {
+			config,
+			context,
+			parents: Vec::new(),
+			objectives: Vec::new(),
+			ranks: Vec::new(),
+			crowding: Vec::new(),
+			offspring,
+			pop_size,
+			generation: 0,
+			seed,
+			rng,
+		}
+	}
+
+	pub fn get_context(&self) -> &G::Context {
+		&self.context
+	}
+
+	pub fn get_seed(&self) -> u64 {
+		self.seed
+	}
+
+	pub fn get_generation(&self) -> usize {
+		self.generation
+	}
+
+	// the survivors of the last evaluation with their objectives
+	pub fn get_population(&self) -> Vec<(&G, &[f64])> {
+		self.parents.iter().zip(self.objectives.iter()).map(|(genome, objectives)| (genome, &objectives[..])).collect()
+	}
+
+	// the survivors no other genome dominates
+	pub fn get_pareto_front(&self) -> Vec<(&G, &[f64])> {
+		self.get_population().into_iter().zip(self.ranks.iter()).filter(|&(_, &rank)| rank == 0).map(|(entry, _)| entry).collect()
+	}
+
+	// NaN objectives count as the worst possible value
+	pub fn calc_fitness<T>(&mut self, task: &T) where T: MultiObjectiveTask<G> + Sync, G: Sync {
+		let seeds: Vec<u64> = (0..self.offspring.len()).map(|_| self.rng.gen()).collect();
+		let mut objectives = vec![Vec::new(); self.offspring.len()];
+		evaluate_genomes(&self.offspring, &seeds, &mut objectives, self.config.workers, |genome, rng| {
+			let mut objectives = task.evaluate_objectives(genome, rng);
+			for objective in objectives.iter_mut().filter(|objective| objective.is_nan()) {
+				*objective = f64::NEG_INFINITY;
+			}
+			objectives
+		});
+
+		let mut pool = mem::take(&mut self.parents);
+		pool.append(&mut self.offspring);
+		let mut pool_objectives = mem::take(&mut self.objectives);
+		pool_objectives.append(&mut objectives);
+
+		self.ranks.clear();
+		self.crowding.clear();
+		for (rank, front) in non_dominated_sort(&pool_objectives).iter().enumerate() {
+			let room = self.pop_size - self.parents.len();
+			if room == 0 {
+				break;
+			}
+			let distances = crowding_distance(&pool_objectives, front);
+			let mut by_crowding: Vec<usize> = (0..front.len()).collect();
+			if front.len() > room {
+				by_crowding.sort_by(|&a, &b| distances[b].partial_cmp(&distances[a]).unwrap_or(Ordering::Equal));
+				by_crowding.truncate(room);
+			}
+			for &i in by_crowding.iter() {
+				self.parents.push(pool[front[i]].clone());
+				self.objectives.push(pool_objectives[front[i]].clone());
+				self.ranks.push(rank);
+				self.crowding.push(distances[i]);
+			}
+		}
+	}
+
+	// call after calc_fitness. parents are picked by binary tournaments on
+	// rank and then crowding distance
+	pub fn produce_new_gen(&mut self) {
+		self.generation += 1;
+		if self.parents.is_empty() {
+			self.offspring = (0..self.pop_size).map(|_| G::random(&mut self.context, &mut self.rng)).collect();
+			return;
+		}
+
+		for _i in 0..self.pop_size {
+			let mut parent1 = self.tournament();
+			let mut parent2 = self.tournament();
+
+			// crossover keeps the structure of the fitter parent
+			if self.crowded_cmp(parent2, parent1) == Ordering::Less {
+				mem::swap(&mut parent1, &mut parent2);
+			}
+			let mut baby = self.parents[parent1].crossover(&self.parents[parent2], &mut self.context, &mut self.rng);
+			baby.mutate(&mut self.context, &mut self.rng);
+			self.offspring.push(baby);
+		}
+	}
+
+	fn tournament(&mut self) -> usize {
+		let a = self.rng.gen::<usize>() % self.parents.len();
+		let b = self.rng.gen::<usize>() % self.parents.len();
+		match self.crowded_cmp(a, b) {
+			Ordering::Greater => b,
+			_ => a,
+		}
+	}
+
+	// Less when parent a is better: a lower rank, or the same rank and a
+	// less crowded spot
+	fn crowded_cmp(&self, a: usize, b: usize) -> Ordering {
+		self.ranks[a].cmp(&self.ranks[b])
+			.then_with(|| self.crowding[b].partial_cmp(&self.crowding[a]).unwrap_or(Ordering::Equal))
+	}
+}
+
+// whether a is at least as good as b on every objective and better on one
+pub fn dominates(a: &[f64], b: &[f64]) -> bool {
+	a.iter().zip(b.iter()).all(|(x, y)| x >= y) && a.iter().zip(b.iter()).any(|(x, y)| x > y)
+}
+
+// splits the indices into fronts, the first is dominated by nothing, the
+// second only by the first and so on
+pub fn non_dominated_sort(objectives: &[Vec<f64>]) -> Vec<Vec<usize>> {
+	let mut dominated_by = vec![0; objectives.len()];
+	let mut dominating: Vec<Vec<usize>> = vec![Vec::new(); objectives.len()];
+	for i in 0..objectives.len() {
+		for j in i + 1..objectives.len() {
+			if dominates(&objectives[i], &objectives[j]) {
+				dominating[i].push(j);
+				dominated_by[j] += 1;
+			} else if dominates(&objectives[j], &objectives[i]) {
+				dominating[j].push(i);
+				dominated_by[i] += 1;
+			}
+		}
+	}
+
+	let mut fronts = Vec::new();
+	let mut front: Vec<usize> = (0..objectives.len()).filter(|&i| dominated_by[i] == 0).collect();
+	while !front.is_empty() {
+		let mut next = Vec::new();
+		for &i in front.iter() {
+			for &j in dominating[i].iter() {
+				dominated_by[j] -= 1;
+				if dominated_by[j] == 0 {
+					next.push(j);
+				}
+			}
+		}
+		next.sort_unstable();
+		fronts.push(front);
+		front = next;
+	}
+	fronts
+}
+
+// how much room each member of a front has around it, the sum over the
+// objectives of the gap between its neighbours relative to the front's
+// range. the ends of each objective get infinity so they are always kept
+pub fn crowding_distance(objectives: &[Vec<f64>], front: &[usize]) -> Vec<f64> {
+	let mut distances = vec![0.0; front.len()];
+	let count = front.first().map_or(0, |&i| objectives[i].len());
+	let value_of = |k: usize, objective: usize| objectives[front[k]][objective];
+	for objective in 0..count {
+		let value = |k: usize| value_of(k, objective);
+		let mut order: Vec<usize> = (0..front.len()).collect();
+		order.sort_by(|&a, &b| value(a).partial_cmp(&value(b)).unwrap_or(Ordering::Equal));
+
+		let (first, last) = (order[0], order[order.len() - 1]);
+		distances[first] = f64::INFINITY;
+		distances[last] = f64::INFINITY;
+		let range = value(last) - value(first);
+		if !(range > 0.0 && range.is_finite()) {
+			continue;
+		}
+		for neighbours in order.windows(3) {
+			distances[neighbours[1]] += (value(neighbours[2]) - value(neighbours[0])) / range;
+		}
+	}
+	distances
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::frozen_lake::FrozenLakeTask;
+	use crate::nn::NN;
+	use crate::population::tests::{neat_context, TEST_POP_SIZE};
+
+	// both objectives are maximised
+	fn points() -> Vec<Vec<f64>> {
+		vec![
+			vec![1.0, 5.0],
+			vec![2.0, 4.0],
+			vec![3.0, 3.0],
+			vec![2.0, 2.0],
+			vec![1.0, 1.0],
+			vec![1.0, 4.0],
+		]
+	}
+
+	#[test]
+	fn fronts_by_hand() {
+		assert!(dominates(&[2.0, 4.0], &[1.0, 4.0]));
+		assert!(!dominates(&[2.0, 2.0], &[1.0, 4.0]));
+		assert!(!dominates(&[3.0, 3.0], &[3.0, 3.0]));
+		assert_eq!(non_dominated_sort(&points()), vec![vec![0, 1, 2], vec![3, 5], vec![4]]);
+	}
+
+	#[test]
+	fn crowding_by_hand() {
+		// the middle point has the whole range of both objectives around it
+		assert_eq!(crowding_distance(&points(), &[0, 1, 2]), vec![f64::INFINITY, 2.0, f64::INFINITY]);
+		assert_eq!(crowding_distance(&points(), &[3, 5]), vec![f64::INFINITY, f64::INFINITY]);
+	}
+
+	#[test]
+	fn pareto_front_is_not_dominated() {
+		let config = Nsga2Config { workers: 1, seed: Some(5) };
+		let mut nsga: Nsga2<NN> = Nsga2::with_config(neat_context(vec![14, 4]), TEST_POP_SIZE, config);
+		for _i in 0..5 {
+			nsga.calc_fitness(&FrozenLakeTask);
+			nsga.produce_new_gen();
+		}
+		nsga.calc_fitness(&FrozenLakeTask);
+
+		let front = nsga.get_pareto_front();
+		assert!(!front.is_empty());
+		for (_, objectives) in front.iter() {
+			assert!(nsga.get_population().iter().all(|(_, other)| !dominates(other, objectives)));
+		}
+	}
+}
@@ -0,0 +1,27 @@
+use crate::genome::Genome;
+use crate::population::Population;
+
+// hooks into a run, every callback does nothing unless overridden. they
+// all run on the thread driving the population, in the order the
+// observers were added
+pub trait Observer<G: Genome> {
+	// called by calc_fitness before any genome is evaluated
+	fn before_evaluation(&mut self, _population: &Population<G>) {}
+
+	// called once per genome with its index in the population, in index
+	// order. with one worker each call comes right after that genome is
+	// evaluated, while the genomes after it still hold last generation's
+	// fitness. with more workers the calls come once the whole generation
+	// is evaluated
+	fn on_evaluated(&mut self, _population: &Population<G>, _index: usize, _genome: &G, _fitness: f64) {}
+
+	// called when a genome beats the best fitness of the run
+	fn on_new_best(&mut self, _population: &Population<G>, _genome: &G, _fitness: f64) {}
+
+	// called by calc_fitness once the fitness, best genome and stats of
+	// the generation are up to date
+	fn after_evaluation(&mut self, _population: &Population<G>) {}
+
+	// called by produce_new_gen once the next generation is in place
+	fn after_reproduction(&mut self, _population: &Population<G>) {}
+}
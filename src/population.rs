@@ -1,32 +1,140 @@
+use crate::genome::Genome;
+use crate::observer::Observer;
 use crate::nn::NN;
-use crate::frozen_lake::{FrozenLake, Direction};
-use rand;
-use std::{thread, time};
+use crate::species::Species;
+use crate::selection::{Selection, FitnessPolicy, FitnessReport};
+use crate::task::Task;
+use crate::rng::SeededRng;
+use crate::stats::GenerationStats;
+use crate::termination::{Termination, StopReason};
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use std::cmp::Ordering;
+use std::thread;
+use std::time::Instant;
+
+mod checkpoint;
+mod island;
+
+pub use self::checkpoint::CheckpointError;
+pub use self::island::{Archipelago, IslandConfig, Topology, MigrantPolicy};
+pub(crate) use self::checkpoint::float;
+use self::checkpoint::{floats, scored};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PopulationConfig {
+	// how parents are picked inside each species
+	pub selection: Selection,
+	// what happens when the fitness is unusable for the selection
+	pub fitness_policy: FitnessPolicy,
+	// genomes closer than this to a species' representative join it. with
+	// the default NEAT distance a new node adds 2 and a new connection 1,
+	// so a few structural mutations found a new species
+	pub compatibility_threshold: f64,
+	// species that have not improved for this many generations are dropped
+	pub stagnation_limit: usize,
+	// this many of the fittest genomes go on to the next generation unchanged
+	pub elitism: usize,
+	// how many of the best distinct genomes of the whole run are kept
+	pub hall_of_fame_size: usize,
+	// threads evaluating fitness, 1 evaluates on the calling thread and
+	// 0 uses one thread per core
+	pub workers: usize,
+	// seed of every random choice in the run, None picks one at random
+	pub seed: Option<u64>,
+	// when the run is over, see should_stop
+	pub termination: Termination,
+}
+
+impl Default for PopulationConfig {
+	fn default() -> PopulationConfig {
+		PopulationConfig {
+			selection: Selection::default(),
+			fitness_policy: FitnessPolicy::default(),
+			compatibility_threshold: 3.0,
+			stagnation_limit: 15,
+			elitism: 0,
+			hall_of_fame_size: 10,
+			workers: 1,
+			seed: None,
+			termination: Termination::default(),
+		}
+	}
+}
 
-pub struct Population {
-	population: Vec<NN>,
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+	serialize = "G: Serialize, G::Context: Serialize",
+	deserialize = "G: Deserialize<'de>, G::Context: Deserialize<'de>",
+))]
+pub struct Population<G: Genome> {
+	config: PopulationConfig,
+	context: G::Context,
+	population: Vec<G>,
+	species: Vec<Species<G>>,
+	next_species_id: usize,
+	#[serde(with = "floats")]
 	pop_fitness: Vec<f64>,
 	pop_size: usize,
 	generation: usize,
-	best_individual: NN,
+	best_individual: G,
+	#[serde(with = "float")]
 	best_fitness: f64,
 	has_solved: bool,
+	// what selection found in the fitness of the last generation
+	fitness_report: FitnessReport,
+	// best first, with the fitness each genome scored
+	#[serde(with = "scored")]
+	hall_of_fame: Vec<(G, f64)>,
+	seed: u64,
+	rng: SeededRng,
+	// one entry per evaluated generation
+	stats: Vec<GenerationStats>,
+	evaluations: usize,
+	// seconds the run took up to last_mark, which is not saved so the
+	// time between a checkpoint and resuming from it is not counted
+	wall_time: f64,
+	#[serde(skip, default = "Instant::now")]
+	last_mark: Instant,
+	#[serde(skip)]
+	observers: Vec<Box<dyn Observer<G>>>,
 }
 
-impl Population {
-	pub fn new(nn_info: Vec<usize>, pop_size: usize) -> Population {
+impl<G: Genome> Population<G> {
+	pub fn new(context: G::Context, pop_size: usize) -> Population<G> {
+		Population::with_config(context, pop_size, PopulationConfig::default())
+	}
+
+	pub fn with_config(mut context: G::Context, pop_size: usize, config: PopulationConfig) -> Population<G> {
+		let seed = config.seed.unwrap_or_else(rand::random);
+		let mut rng = SeededRng::new(seed);
+		let best_individual = G::random(&mut context, &mut rng);
 		let mut population = Population {
+			config,
+			context,
 			population: Vec::new(),
+			species: Vec::new(),
+			next_species_id: 0,
 			pop_fitness: Vec::new(),
 			pop_size: pop_size,
 			generation: 0,
-			best_individual: NN::new(nn_info.clone()),
-			best_fitness: 0.0,
+			best_individual,
+			best_fitness: f64::NEG_INFINITY,
 			has_solved: false,
+			fitness_report: FitnessReport::default(),
+			hall_of_fame: Vec::new(),
+			seed,
+			rng,
+			stats: Vec::new(),
+			evaluations: 0,
+			wall_time: 0.0,
+			last_mark: Instant::now(),
+			observers: Vec::new(),
 		};
 
 		for _i in 0..pop_size {
-			population.population.push(NN::new(nn_info.clone()));
+			let genome = G::random(&mut population.context, &mut population.rng);
+			population.population.push(genome);
 		}
 
 		for _i in 0..pop_size {
@@ -36,6 +144,20 @@ impl Population {
 		population
 	}
 
+	// observers are not saved in checkpoints, add them again after loading
+	pub fn add_observer(&mut self, observer: Box<dyn Observer<G>>) {
+		self.observers.push(observer);
+	}
+
+	pub fn get_context(&self) -> &G::Context {
+		&self.context
+	}
+
+	// replaying a run needs this seed and the same config
+	pub fn get_seed(&self) -> u64 {
+		self.seed
+	}
+
 	pub fn get_has_solved(&self) -> bool {
 		self.has_solved
 	}
@@ -44,6 +166,14 @@ impl Population {
 		self.generation
 	}
 
+	pub fn get_species(&self) -> &[Species<G>] {
+		&self.species
+	}
+
+	pub fn get_fitness_report(&self) -> &FitnessReport {
+		&self.fitness_report
+	}
+
 	pub fn get_average_fitness(&self) -> f64 {
 		let mut total_fitness = 0.0;
 
@@ -54,155 +184,401 @@ impl Population {
 		total_fitness / self.pop_size as f64
 	}
 
-	pub fn print_best_individual(&self) {
-		self.best_individual.print_nodes();
-		self.best_individual.print_connections();
+	pub fn get_best_individual(&self) -> &G {
+		&self.best_individual
 	}
 
-	pub fn calc_fitness(&mut self) {
-		for i in 0..self.pop_size {
-			let mut frozen_lake = FrozenLake::new();
-			while !frozen_lake.is_game_over() {
-				let left = frozen_lake.get_left();
-				let right = frozen_lake.get_right();
-				let up = frozen_lake.get_up();
-				let down = frozen_lake.get_down();
-
-				let mut inputs = Vec::new();
-
-				inputs.push((left == 'F' || left == 'S') as i64 as f64); // input 1
-				inputs.push((left == 'H') as i64 as f64); // input 2
-				inputs.push((left == 'G') as i64 as f64); //input 3
-				inputs.push((right == 'F' || right == 'S') as i64 as f64); // input 4
-				inputs.push((right == 'H') as i64 as f64); // input 5
-				inputs.push((right == 'G') as i64 as f64); // input 6
-				inputs.push((up == 'F' || up == 'S') as i64 as f64); // input 7
-				inputs.push((up == 'H') as i64 as f64); // input 8
-				inputs.push((up == 'G') as i64 as f64); // input 9
-				inputs.push((down == 'F' || down == 'S') as i64 as f64); // input 10
-				inputs.push((down == 'H') as i64 as f64); // input 11
-				inputs.push((down == 'G') as i64 as f64); // input 12
-				inputs.push(frozen_lake.get_x_diff_from_g()); // input 13
-				inputs.push(frozen_lake.get_y_diff_from_g()); // input 14
-
-				let outputs = self.population[i].feed_forward(inputs).unwrap();
-				let up_d = outputs[0];
-				let down_d = outputs[1];
-				let left_d = outputs[2];
-				let right_d = outputs[3];
-
-				let mut direction = Direction::Left;
-
-				if up_d >= down_d && up_d >= left_d && up_d >= right_d {
-					direction = Direction::Up;
-				} else if down_d >= up_d && down_d >= left_d && down_d >= right_d {
-					direction = Direction::Down;
-				} else if left_d >= up_d && left_d >= down_d && left_d >= right_d {
-					direction = Direction::Left;
-				} else if right_d >= up_d && right_d >= down_d && right_d >= left_d {
-					direction = Direction::Right;
-				}
+	pub fn get_hall_of_fame(&self) -> &[(G, f64)] {
+		&self.hall_of_fame
+	}
 
-				frozen_lake.move_player(direction);
-			}
-			let mut fitness: f64 = 8.0 - frozen_lake.get_x_diff_from_g().abs() - frozen_lake.get_y_diff_from_g().abs();
-			fitness = fitness.exp2() + frozen_lake.get_num_moves() as f64;
+	pub fn get_stats(&self) -> &[GenerationStats] {
+		&self.stats
+	}
 
-			self.pop_fitness[i] = fitness;
+	pub fn get_evaluations(&self) -> usize {
+		self.evaluations
+	}
 
-			if fitness > self.best_fitness {
-				self.best_fitness = fitness;
-				self.best_individual = self.population[i].clone();
+	// every genome is evaluated on its own, with its own random numbers,
+	// and its fitness stored at its index, so the result does not depend
+	// on the number of workers
+	pub fn calc_fitness<T>(&mut self, task: &T) where T: Task<G> + Sync, G: Sync {
+		self.notify(|observer, population| observer.before_evaluation(population));
+
+		let seeds: Vec<u64> = (0..self.pop_size).map(|_| self.rng.gen()).collect();
+		if worker_count(self.config.workers) <= 1 {
+			// on the calling thread observers hear about each genome right away
+			for (i, &seed) in seeds.iter().enumerate() {
+				self.pop_fitness[i] = task.evaluate(&self.population[i], &mut SeededRng::new(seed));
+				self.notify(|observer, population| {
+					observer.on_evaluated(population, i, &population.population[i], population.pop_fitness[i]);
+				});
 			}
+		} else {
+			evaluate_genomes(&self.population, &seeds, &mut self.pop_fitness, self.config.workers, |genome, rng| task.evaluate(genome, rng));
+			self.notify(|observer, population| {
+				for (i, genome) in population.population.iter().enumerate() {
+					observer.on_evaluated(population, i, genome, population.pop_fitness[i]);
+				}
+			});
+		}
+
+		if self.pop_fitness.iter().any(|&fitness| task.is_solved(fitness)) {
+			self.has_solved = true;
+		}
+		self.evaluations += self.pop_size;
+		self.update_best();
+		self.record_stats();
+		self.notify(|observer, population| observer.after_evaluation(population));
+	}
+
+	fn record_stats(&mut self) {
+		let now = Instant::now();
+		self.wall_time += now.duration_since(self.last_mark).as_secs_f64();
+		self.last_mark = now;
+
+		let stats = GenerationStats::new(
+			self.generation,
+			&self.pop_fitness,
+			self.best_fitness,
+			diversity(&self.population.iter().collect::<Vec<_>>(), &self.context),
+			self.evaluations,
+			self.wall_time,
+		);
+		self.stats.push(stats);
+	}
 
-			if fitness >= 256.0 {
-				self.has_solved = true;
+
+	// checks the termination criteria against the generations evaluated
+	// so far, call it after calc_fitness
+	pub fn should_stop(&self) -> Option<StopReason> {
+		self.config.termination.check(&self.stats, self.has_solved)
+	}
+
+	// call once pop_fitness holds this generation's fitness
+	fn update_best(&mut self) {
+		let mut improved = false;
+		for i in 0..self.pop_size {
+			if self.pop_fitness[i] > self.best_fitness {
+				self.best_fitness = self.pop_fitness[i];
+				self.best_individual = self.population[i].clone();
+				improved = true;
 			}
 		}
+		self.update_hall_of_fame();
+		if improved {
+			self.notify(|observer, population| observer.on_new_best(population, &population.best_individual, population.best_fitness));
+		}
 	}
 
-	pub fn play_best_individual(&mut self) {
-		let mut frozen_lake = FrozenLake::new();
-		frozen_lake.print_board();
-		while !frozen_lake.is_game_over() {
-			let left = frozen_lake.get_left();
-			let right = frozen_lake.get_right();
-			let up = frozen_lake.get_up();
-			let down = frozen_lake.get_down();
-
-			let mut inputs = Vec::new();
-
-			inputs.push((left == 'F' || left == 'S') as i64 as f64); // input 1
-			inputs.push((left == 'H') as i64 as f64); // input 2
-			inputs.push((left == 'G') as i64 as f64); //input 3
-			inputs.push((right == 'F' || right == 'S') as i64 as f64); // input 4
-			inputs.push((right == 'H') as i64 as f64); // input 5
-			inputs.push((right == 'G') as i64 as f64); // input 6
-			inputs.push((up == 'F' || up == 'S') as i64 as f64); // input 7
-			inputs.push((up == 'H') as i64 as f64); // input 8
-			inputs.push((up == 'G') as i64 as f64); // input 9
-			inputs.push((down == 'F' || down == 'S') as i64 as f64); // input 10
-			inputs.push((down == 'H') as i64 as f64); // input 11
-			inputs.push((down == 'G') as i64 as f64); // input 12
-			inputs.push(frozen_lake.get_x_diff_from_g()); // input 13
-			inputs.push(frozen_lake.get_y_diff_from_g()); // input 14
-
-			let outputs = self.best_individual.feed_forward(inputs).unwrap();
-			let up_d = outputs[0];
-			let down_d = outputs[1];
-			let left_d = outputs[2];
-			let right_d = outputs[3];
-
-			let mut direction = Direction::Left;
-
-			if up_d >= down_d && up_d >= left_d && up_d >= right_d {
-				direction = Direction::Up;
-			} else if down_d >= up_d && down_d >= left_d && down_d >= right_d {
-				direction = Direction::Down;
-			} else if left_d >= up_d && left_d >= down_d && left_d >= right_d {
-				direction = Direction::Left;
-			} else if right_d >= up_d && right_d >= down_d && right_d >= left_d {
-				direction = Direction::Right;
+	// a genome only enters if no identical genome is in there already
+	fn update_hall_of_fame(&mut self) {
+		for i in 0..self.pop_size {
+			let fitness = self.pop_fitness[i];
+			let full = self.hall_of_fame.len() >= self.config.hall_of_fame_size;
+			if full && self.hall_of_fame.last().is_none_or(|&(_, worst)| fitness <= worst) {
+				continue;
+			}
+			let genome = &self.population[i];
+			if self.hall_of_fame.iter().any(|(other, _)| genome == other) {
+				continue;
 			}
 
-			frozen_lake.move_player(direction);
-			thread::sleep(time::Duration::new(2, 0));
-			frozen_lake.print_board();
+			let position = self.hall_of_fame.iter().position(|&(_, f)| fitness > f).unwrap_or(self.hall_of_fame.len());
+			self.hall_of_fame.insert(position, (genome.clone(), fitness));
+			self.hall_of_fame.truncate(self.config.hall_of_fame_size);
 		}
 	}
 
-	pub fn produce_new_gen(&mut self) {
+	// fails without touching the population when the fitness policy is
+	// Error and the fitness is degenerate
+	pub fn produce_new_gen(&mut self) -> Result<(), FitnessReport> {
+		self.fitness_report = self.config.selection.inspect(&self.pop_fitness);
+		let uniform = self.fitness_report.is_degenerate() && match self.config.fitness_policy {
+			FitnessPolicy::Error => return Err(self.fitness_report.clone()),
+			FitnessPolicy::Clamp => false,
+			FitnessPolicy::Uniform => true,
+		};
+
 		self.generation += 1;
-		let mut new_population: Vec<NN> = Vec::new();
+		self.speciate();
+		self.cull_stagnant_species();
+
+		let mut ranked: Vec<usize> = (0..self.pop_size).collect();
+		ranked.sort_by(|&a, &b| self.pop_fitness[b].partial_cmp(&self.pop_fitness[a]).unwrap_or(Ordering::Equal));
+		let elites = self.config.elitism.min(self.pop_size);
+		let mut new_population: Vec<G> = ranked[..elites].iter().map(|&i| self.population[i].clone()).collect();
 
-		for _i in 0..self.pop_size {
-			let mut parent1: usize = 0;
-			let mut parent2: usize = 0;
-			let mut found_parent1 = false;
-			let mut found_parent2 = false;
+		let offspring = self.allocate_offspring(self.pop_size - elites);
 
-			while !found_parent1 {
-				let random_index = rand::random::<usize>() % self.pop_size;
-				let accept_reject = rand::random::<f64>() * self.best_fitness;
+		for (species, &count) in offspring.iter().enumerate() {
+			let parents = self.select_parents(species, count * 2, uniform);
+			for pair in parents.chunks(2) {
+				let (mut parent1, mut parent2) = (pair[0], pair[1]);
 
-				if accept_reject < self.pop_fitness[random_index] {
-					found_parent1 = true;
-					parent1 = random_index;
+				// crossover keeps the structure of the fitter parent
+				if self.pop_fitness[parent2] > self.pop_fitness[parent1] {
+					std::mem::swap(&mut parent1, &mut parent2);
 				}
+				let mut baby = self.population[parent1].crossover(&self.population[parent2], &mut self.context, &mut self.rng);
+				baby.mutate(&mut self.context, &mut self.rng);
+
+				new_population.push(baby);
 			}
+		}
+		self.population = new_population;
+		self.notify(|observer, population| observer.after_reproduction(population));
+		Ok(())
+	}
 
-			while !found_parent2 {
-				let random_index = rand::random::<usize>() % self.pop_size;
-				let accept_reject = rand::random::<f64>() * self.best_fitness;
+	// observers only get a shared population, so they are taken out while
+	// they run
+	fn notify<F: FnMut(&mut dyn Observer<G>, &Population<G>)>(&mut self, mut callback: F) {
+		let mut observers = std::mem::take(&mut self.observers);
+		for observer in observers.iter_mut() {
+			callback(observer.as_mut(), self);
+		}
+		self.observers = observers;
+	}
+
+	// puts every genome into the first species whose representative is
+	// close enough, or into a new species of its own
+	fn speciate(&mut self) {
+		for species in self.species.iter_mut() {
+			species.clear_members();
+		}
 
-				if accept_reject < self.pop_fitness[random_index] {
-					found_parent2 = true;
-					parent2 = random_index;
+		for i in 0..self.pop_size {
+			let mut found_species = false;
+			for species in self.species.iter_mut() {
+				let distance = self.population[i].distance(species.get_representative(), &self.context);
+				if distance < self.config.compatibility_threshold {
+					species.add_member(i);
+					found_species = true;
+					break;
 				}
 			}
 
-			new_population.push(self.population[parent1].crossover(self.population[parent2].clone()));
+			if !found_species {
+				let mut species = Species::new(self.next_species_id, self.population[i].clone());
+				species.add_member(i);
+				self.species.push(species);
+				self.next_species_id += 1;
+			}
 		}
-		self.population = new_population;
+
+		self.species.retain(|s| s.get_size() > 0);
+		for species in self.species.iter_mut() {
+			species.update(&self.population, &self.pop_fitness, &mut self.rng);
+		}
+	}
+
+	// the species holding this generation's best genome is never dropped
+	fn cull_stagnant_species(&mut self) {
+		let mut best = 0;
+		for i in 0..self.pop_size {
+			if self.pop_fitness[i] > self.pop_fitness[best] {
+				best = i;
+			}
+		}
+
+		let stagnation_limit = self.config.stagnation_limit;
+		self.species.retain(|s| {
+			s.get_stagnant_generations() < stagnation_limit || s.get_members().contains(&best)
+		});
+	}
+
+	// each species gets children in proportion to its shared fitness
+	fn allocate_offspring(&self, children: usize) -> Vec<usize> {
+		let shares: Vec<f64> = self.species.iter()
+			.map(|s| s.get_shared_fitness(&self.pop_fitness).max(0.0))
+			.collect();
+		let total: f64 = shares.iter().sum();
+
+		let quotas: Vec<f64> = if total > 0.0 && total.is_finite() {
+			shares.iter().map(|share| share / total * children as f64).collect()
+		} else {
+			vec![children as f64 / self.species.len() as f64; self.species.len()]
+		};
+
+		let mut offspring: Vec<usize> = quotas.iter().map(|q| q.floor() as usize).collect();
+		let assigned: usize = offspring.iter().sum();
+
+		// hand what rounding left over to the largest remainders
+		let mut by_remainder: Vec<usize> = (0..quotas.len()).collect();
+		by_remainder.sort_by(|&a, &b| {
+			(quotas[b] - quotas[b].floor()).partial_cmp(&(quotas[a] - quotas[a].floor())).unwrap()
+		});
+		for &species in by_remainder.iter().cycle().take(children.saturating_sub(assigned)) {
+			offspring[species] += 1;
+		}
+
+		offspring
+	}
+
+	// population indices of count parents from one species
+	fn select_parents(&mut self, species: usize, count: usize, uniform: bool) -> Vec<usize> {
+		let members = self.species[species].get_members();
+		let rng = &mut self.rng;
+		if uniform {
+			return (0..count).map(|_| members[rng.gen::<usize>() % members.len()]).collect();
+		}
+		let pop_fitness = &self.pop_fitness;
+		let fitness: Vec<f64> = members.iter().map(|&i| pop_fitness[i]).collect();
+		self.config.selection.select(&fitness, count, rng).iter().map(|&i| members[i]).collect()
+	}
+}
+
+impl Population<NN> {
+	pub fn print_best_individual(&self) {
+		self.best_individual.print_nodes();
+		self.best_individual.print_connections();
+	}
+}
+
+// distances are too slow to compare every pair of a large population
+const DIVERSITY_PAIRS: usize = 256;
+
+// mean distance between pairs of genomes, taken over at most
+// DIVERSITY_PAIRS pairs spread evenly over all of them
+fn diversity<G: Genome>(genomes: &[&G], context: &G::Context) -> f64 {
+	let pairs = genomes.len() * genomes.len().saturating_sub(1) / 2;
+	if pairs == 0 {
+		return 0.0;
+	}
+	let step = pairs.div_ceil(DIVERSITY_PAIRS);
+
+	let mut total = 0.0;
+	let mut sampled = 0;
+	let mut pair = 0;
+	for (i, genome) in genomes.iter().enumerate() {
+		for other in genomes[i + 1..].iter() {
+			if pair % step == 0 {
+				total += genome.distance(other, context);
+				sampled += 1;
+			}
+			pair += 1;
+		}
+	}
+	total / sampled as f64
+}
+
+// the threads workers asks for, see PopulationConfig::workers
+fn worker_count(workers: usize) -> usize {
+	match workers {
+		0 => thread::available_parallelism().map_or(1, |n| n.get()),
+		workers => workers,
 	}
-}
\ No newline at end of file
+}
+
+// runs evaluate on every genome with an rng seeded from its seed and
+// stores the result at the genome's index, on as many threads as workers
+// asks for, see PopulationConfig::workers
+pub(crate) fn evaluate_genomes<G, T, F>(genomes: &[G], seeds: &[u64], results: &mut [T], workers: usize, evaluate: F)
+where
+	G: Sync,
+	T: Send,
+	F: Fn(&G, &mut SeededRng) -> T + Sync,
+{
+	let workers = worker_count(workers);
+
+	let evaluate_all = |genomes: &[G], seeds: &[u64], results: &mut [T]| {
+		for ((genome, &seed), result) in genomes.iter().zip(seeds.iter()).zip(results.iter_mut()) {
+			*result = evaluate(genome, &mut SeededRng::new(seed));
+		}
+	};
+	if workers <= 1 {
+		evaluate_all(genomes, seeds, results);
+		return;
+	}
+
+	let chunk = genomes.len().div_ceil(workers).max(1);
+	thread::scope(|scope| {
+		let chunks = genomes.chunks(chunk).zip(seeds.chunks(chunk)).zip(results.chunks_mut(chunk));
+		for ((genomes, seeds), results) in chunks {
+			let evaluate_all = &evaluate_all;
+			scope.spawn(move || evaluate_all(genomes, seeds, results));
+		}
+	});
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use crate::nn::{NeatConfig, NeatContext};
+	use crate::selection::tests::{degenerate_fitness, STRATEGIES};
+	use crate::snake::SnakeTask;
+
+	// genomes in the populations the tests evolve
+	pub(crate) const TEST_POP_SIZE: usize = 20;
+
+	pub(crate) fn neat_context(nn_info: Vec<usize>) -> NeatContext {
+		NeatContext::new(nn_info, NeatConfig::default()).unwrap()
+	}
+
+	pub(crate) fn seeded_population(nn_info: Vec<usize>, seed: u64, config: PopulationConfig) -> Population<NN> {
+		let config = PopulationConfig { seed: Some(seed), ..config };
+		Population::with_config(neat_context(nn_info), TEST_POP_SIZE, config)
+	}
+
+	// evaluates and breeds this many generations
+	pub(crate) fn advance<T: Task<NN> + Sync>(population: &mut Population<NN>, task: &T, generations: usize) {
+		for _i in 0..generations {
+			population.calc_fitness(task);
+			population.produce_new_gen().unwrap();
+		}
+	}
+
+	fn run(workers: usize) -> Population<NN> {
+		let task = SnakeTask::new(50);
+		let mut population = seeded_population(vec![6, 4], 42, PopulationConfig { workers, ..PopulationConfig::default() });
+		advance(&mut population, &task, 5);
+		population.calc_fitness(&task);
+		population
+	}
+
+	#[test]
+	fn error_policy_leaves_the_population_alone() {
+		let config = PopulationConfig { fitness_policy: FitnessPolicy::Error, ..PopulationConfig::default() };
+		for fitness in degenerate_fitness(TEST_POP_SIZE) {
+			let mut population = seeded_population(vec![2, 1], 3, config.clone());
+			population.pop_fitness = fitness.clone();
+			let before = population.population.clone();
+			let rng = population.rng.clone();
+
+			assert_eq!(population.produce_new_gen(), Err(population.config.selection.inspect(&fitness)));
+			assert!(population.population == before);
+			assert_eq!(population.rng, rng);
+			assert_eq!(population.generation, 0);
+		}
+	}
+
+	#[test]
+	fn clamp_and_uniform_policies_still_breed() {
+		for &fitness_policy in [FitnessPolicy::Clamp, FitnessPolicy::Uniform].iter() {
+			for &selection in STRATEGIES.iter() {
+				let config = PopulationConfig { fitness_policy, selection, ..PopulationConfig::default() };
+				for fitness in degenerate_fitness(TEST_POP_SIZE) {
+					let mut population = seeded_population(vec![2, 1], 3, config.clone());
+					population.pop_fitness = fitness;
+					population.produce_new_gen().unwrap();
+					assert_eq!(population.population.len(), TEST_POP_SIZE);
+					assert_eq!(population.generation, 1);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn workers_do_not_change_the_run() {
+		let single = run(1);
+		let parallel = run(4);
+		// the genomes have to differ for the comparison to mean anything
+		assert!(single.pop_fitness.iter().any(|&fitness| fitness != single.pop_fitness[0]));
+		assert!(single.population == parallel.population);
+		assert_eq!(single.pop_fitness, parallel.pop_fitness);
+		assert!(single.best_individual == parallel.best_individual);
+		assert_eq!(single.best_fitness, parallel.best_fitness);
+	}
+}
@@ -0,0 +1,200 @@
+use serde::{de, Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+use crate::genome::Genome;
+use super::Population;
+
+// checkpoints carry a version like genome files do
+const FORMAT: &str = "genetic-algorithm-checkpoint";
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckpointError {
+	Io(String),
+	Invalid(String),
+}
+
+impl fmt::Display for CheckpointError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CheckpointError::Io(reason) => write!(f, "{}", reason),
+			CheckpointError::Invalid(reason) => write!(f, "Invalid checkpoint: {}", reason),
+		}
+	}
+}
+
+impl Error for CheckpointError {}
+
+#[derive(Deserialize)]
+struct Header {
+	format: String,
+	version: u32,
+}
+
+#[derive(Serialize)]
+#[serde(bound = "G: Serialize, G::Context: Serialize")]
+struct CheckpointRef<'a, G: Genome> {
+	format: &'a str,
+	version: u32,
+	population: &'a Population<G>,
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "G: DeserializeOwned, G::Context: DeserializeOwned")]
+struct CheckpointFile<G: Genome> {
+	population: Population<G>,
+}
+
+impl<G: Genome> Population<G> where G: Serialize, G::Context: Serialize {
+	// everything the run needs to go on, including the RNG, so a resumed
+	// run continues exactly as if it had never stopped. the file is written
+	// next to path first and then moved over it, so a crash while saving
+	// keeps the previous checkpoint intact
+	pub fn save_checkpoint(&self, path: &str) -> Result<(), CheckpointError> {
+		let checkpoint = CheckpointRef {
+			format: FORMAT,
+			version: VERSION,
+			population: self,
+		};
+		let json = serde_json::to_string(&checkpoint).map_err(|e| CheckpointError::Invalid(e.to_string()))?;
+
+		let temporary = format!("{}.tmp", path);
+		fs::write(&temporary, json).map_err(|e| CheckpointError::Io(format!("Could not write {}: {}", temporary, e)))?;
+		fs::rename(&temporary, path).map_err(|e| CheckpointError::Io(format!("Could not write {}: {}", path, e)))
+	}
+}
+
+impl<G: Genome> Population<G> where G: DeserializeOwned, G::Context: DeserializeOwned {
+	pub fn load_checkpoint(path: &str) -> Result<Population<G>, CheckpointError> {
+		let json = fs::read_to_string(path).map_err(|e| CheckpointError::Io(format!("Could not read {}: {}", path, e)))?;
+
+		let header: Header = serde_json::from_str(&json)
+			.map_err(|_| CheckpointError::Invalid(format!("{} is not a checkpoint.", path)))?;
+		if header.format != FORMAT {
+			return Err(CheckpointError::Invalid(format!("Not a checkpoint, format is \"{}\".", header.format)));
+		}
+		if header.version != VERSION {
+			return Err(CheckpointError::Invalid(format!("Unsupported version {}, expected {}.", header.version, VERSION)));
+		}
+
+		let file: CheckpointFile<G> = serde_json::from_str(&json).map_err(|e| CheckpointError::Invalid(e.to_string()))?;
+		Ok(file.population)
+	}
+}
+
+// JSON has no NaN or infinity, so fitness is written as a number when it
+// is finite and as "NaN", "inf" or "-inf" otherwise
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Float {
+	Number(f64),
+	Text(String),
+}
+
+impl Float {
+	fn value<E: de::Error>(self) -> Result<f64, E> {
+		match self {
+			Float::Number(x) => Ok(x),
+			Float::Text(text) => text.parse().map_err(|_| E::custom(format!("\"{}\" is not a number.", text))),
+		}
+	}
+}
+
+fn serialize_f64<S: Serializer>(x: f64, serializer: S) -> Result<S::Ok, S::Error> {
+	if x.is_finite() {
+		serializer.serialize_f64(x)
+	} else {
+		serializer.serialize_str(&x.to_string())
+	}
+}
+
+struct Finite(f64);
+
+impl Serialize for Finite {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serialize_f64(self.0, serializer)
+	}
+}
+
+pub mod float {
+	use super::*;
+
+	pub fn serialize<S: Serializer>(x: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+		serialize_f64(*x, serializer)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+		Float::deserialize(deserializer)?.value()
+	}
+}
+
+pub mod floats {
+	use super::*;
+
+	pub fn serialize<S: Serializer>(xs: &[f64], serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(xs.iter().map(|&x| Finite(x)))
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f64>, D::Error> {
+		Vec::<Float>::deserialize(deserializer)?.into_iter().map(Float::value).collect()
+	}
+}
+
+// genomes paired with their fitness, like the hall of fame
+pub mod scored {
+	use super::*;
+
+	pub fn serialize<G: Serialize, S: Serializer>(entries: &[(G, f64)], serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(entries.iter().map(|(genome, fitness)| (genome, Finite(*fitness))))
+	}
+
+	pub fn deserialize<'de, G: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(G, f64)>, D::Error> {
+		Vec::<(G, Float)>::deserialize(deserializer)?
+			.into_iter()
+			.map(|(genome, fitness)| Ok((genome, fitness.value()?)))
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::nn::NN;
+	use crate::population::PopulationConfig;
+	use crate::population::tests::{seeded_population, advance};
+	use crate::snake::SnakeTask;
+
+	#[test]
+	fn resumed_run_continues_unchanged() {
+		let task = SnakeTask::new(50);
+		let mut population = seeded_population(vec![6, 4], 7, PopulationConfig::default());
+		advance(&mut population, &task, 3);
+
+		let path = std::env::temp_dir().join(format!("checkpoint-test-{}.json", std::process::id()));
+		let path = path.to_str().unwrap();
+		population.save_checkpoint(path).unwrap();
+		let mut resumed: Population<NN> = Population::load_checkpoint(path).unwrap();
+		fs::remove_file(path).unwrap();
+
+		advance(&mut population, &task, 3);
+		advance(&mut resumed, &task, 3);
+		assert!(population.population == resumed.population);
+		assert_eq!(population.pop_fitness, resumed.pop_fitness);
+		assert!(population.best_individual == resumed.best_individual);
+		assert_eq!(population.rng, resumed.rng);
+		assert_eq!(population.generation, resumed.generation);
+	}
+
+	#[test]
+	fn other_files_are_rejected() {
+		let path = std::env::temp_dir().join(format!("checkpoint-test-other-{}.json", std::process::id()));
+		let path = path.to_str().unwrap();
+		fs::write(path, "{\"format\": \"something-else\", \"version\": 1}").unwrap();
+		let loaded: Result<Population<NN>, CheckpointError> = Population::load_checkpoint(path);
+		fs::remove_file(path).unwrap();
+		assert!(matches!(loaded, Err(CheckpointError::Invalid(_))));
+	}
+}
@@ -0,0 +1,283 @@
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use std::cmp::Ordering;
+use std::mem;
+use std::time::Instant;
+
+use crate::genome::Genome;
+use crate::rng::SeededRng;
+use crate::selection::{Selection, FitnessPolicy, FitnessReport};
+use crate::stats::GenerationStats;
+use crate::task::Task;
+use crate::termination::{Termination, StopReason};
+use super::{Population, PopulationConfig, diversity};
+
+// which islands send migrants to which
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+	// every island sends to the next one, the last to the first
+	#[default]
+	Ring,
+	// every island sends to every other island
+	FullyConnected,
+	// every island sends to one other island drawn at each migration
+	Random,
+}
+
+// which genomes of an island leave it
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrantPolicy {
+	// the fittest genomes
+	#[default]
+	Best,
+	// uniformly drawn genomes
+	Random,
+	// genomes picked like parents are
+	Select(Selection),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IslandConfig {
+	pub topology: Topology,
+	// migrants move after every this many generations, 0 never migrates
+	pub migration_interval: usize,
+	// how many genomes each island sends per destination
+	pub migrants: usize,
+	pub migrant_policy: MigrantPolicy,
+	// when the whole run is over, the termination of each island's
+	// PopulationConfig is ignored
+	pub termination: Termination,
+	// seeds the islands, None picks one at random
+	pub seed: Option<u64>,
+}
+
+impl Default for IslandConfig {
+	fn default() -> IslandConfig {
+		IslandConfig {
+			topology: Topology::default(),
+			migration_interval: 10,
+			migrants: 2,
+			migrant_policy: MigrantPolicy::default(),
+			termination: Termination::default(),
+			seed: None,
+		}
+	}
+}
+
+// populations evolving on their own that swap a few genomes now and then.
+// they share one context, so NEAT innovation numbers mean the same on
+// every island and migrants line up with the natives
+pub struct Archipelago<G: Genome> {
+	config: IslandConfig,
+	// lent to each island while it evolves, the islands' own contexts are
+	// only placeholders
+	context: G::Context,
+	islands: Vec<Population<G>>,
+	generation: usize,
+	// statistics over all islands together
+	stats: Vec<GenerationStats>,
+	wall_time: f64,
+	last_mark: Instant,
+	seed: u64,
+	rng: SeededRng,
+}
+
+impl<G: Genome> Archipelago<G> where G::Context: Clone {
+	pub fn new(context: G::Context, islands: usize, island_size: usize) -> Archipelago<G> {
+		Archipelago::with_config(context, islands, island_size, PopulationConfig::default(), IslandConfig::default())
+	}
+
+	// every island uses population_config, with a seed drawn from the
+	// archipelago's own
+	pub fn with_config(mut context: G::Context, islands: usize, island_size: usize, population_config: PopulationConfig, config: IslandConfig) -> Archipelago<G> {
+		let seed = config.seed.unwrap_or_else(rand::random);
+		let mut rng = SeededRng::new(seed);
+
+		let islands = (0..islands).map(|_| {
+			let island_config = PopulationConfig { seed: Some(rng.gen()), ..population_config.clone() };
+			let placeholder = context.clone();
+			let mut island = Population::with_config(context.clone(), island_size, island_config);
+			context = mem::replace(&mut island.context, placeholder);
+			island
+		}).collect();
+
+		Archipelago {
+			config,
+			context,
+			islands,
+			generation: 0,
+			stats: Vec::new(),
+			wall_time: 0.0,
+			last_mark: Instant::now(),
+			seed,
+			rng,
+		}
+	}
+}
+
+impl<G: Genome> Archipelago<G> {
+	pub fn get_context(&self) -> &G::Context {
+		&self.context
+	}
+
+	pub fn get_seed(&self) -> u64 {
+		self.seed
+	}
+
+	pub fn get_generation(&self) -> usize {
+		self.generation
+	}
+
+	pub fn get_islands(&self) -> &[Population<G>] {
+		&self.islands
+	}
+
+	pub fn get_stats(&self) -> &[GenerationStats] {
+		&self.stats
+	}
+
+	pub fn get_has_solved(&self) -> bool {
+		self.islands.iter().any(|island| island.has_solved)
+	}
+
+	pub fn get_average_fitness(&self) -> f64 {
+		let total: f64 = self.islands.iter().flat_map(|island| island.pop_fitness.iter()).sum();
+		let size: usize = self.islands.iter().map(|island| island.pop_size).sum();
+		total / size as f64
+	}
+
+	// the best genome of the run on any island
+	pub fn get_best_individual(&self) -> &G {
+		let best = self.islands.iter()
+			.max_by(|a, b| a.best_fitness.partial_cmp(&b.best_fitness).unwrap_or(Ordering::Equal))
+			.expect("an archipelago has islands");
+		&best.best_individual
+	}
+
+	pub fn calc_fitness<T>(&mut self, task: &T) where T: Task<G> + Sync, G: Sync {
+		for i in 0..self.islands.len() {
+			self.on_island(i, |island| island.calc_fitness(task));
+		}
+		self.record_stats();
+	}
+
+	pub fn should_stop(&self) -> Option<StopReason> {
+		self.config.termination.check(&self.stats, self.get_has_solved())
+	}
+
+	// migrates when it is time to and then breeds every island. when one
+	// of them would refuse to breed, with the migrants it took in, it fails
+	// before any island has bred, though migration has already happened
+	pub fn produce_new_gen(&mut self) -> Result<(), FitnessReport> {
+		let interval = self.config.migration_interval;
+		if interval > 0 && (self.generation + 1).is_multiple_of(interval) {
+			self.migrate();
+		}
+
+		for island in self.islands.iter() {
+			let report = island.config.selection.inspect(&island.pop_fitness);
+			if report.is_degenerate() && island.config.fitness_policy == FitnessPolicy::Error {
+				return Err(report);
+			}
+		}
+
+		for i in 0..self.islands.len() {
+			self.on_island(i, |island| island.produce_new_gen())?;
+		}
+		self.generation += 1;
+		Ok(())
+	}
+
+	fn on_island<T, F: FnOnce(&mut Population<G>) -> T>(&mut self, i: usize, f: F) -> T {
+		let island = &mut self.islands[i];
+		mem::swap(&mut self.context, &mut island.context);
+		let result = f(island);
+		mem::swap(&mut self.context, &mut island.context);
+		result
+	}
+
+	// every island picks its migrants before any arrive, so a genome moves
+	// at most one step per migration
+	fn migrate(&mut self) {
+		let count = self.islands.len();
+		if count < 2 || self.config.migrants == 0 {
+			return;
+		}
+
+		let (policy, migrants) = (self.config.migrant_policy, self.config.migrants);
+		let outgoing: Vec<Vec<(G, f64)>> = self.islands.iter_mut().map(|island| island.emigrants(policy, migrants)).collect();
+
+		let mut incoming: Vec<Vec<(G, f64)>> = vec![Vec::new(); count];
+		for (from, migrants) in outgoing.into_iter().enumerate() {
+			for to in self.destinations(from) {
+				incoming[to].extend(migrants.iter().cloned());
+			}
+		}
+
+		for (island, migrants) in self.islands.iter_mut().zip(incoming) {
+			island.immigrate(migrants);
+		}
+	}
+
+	fn destinations(&mut self, from: usize) -> Vec<usize> {
+		let count = self.islands.len();
+		match self.config.topology {
+			Topology::Ring => vec![(from + 1) % count],
+			Topology::FullyConnected => (0..count).filter(|&to| to != from).collect(),
+			Topology::Random => {
+				let to = self.rng.gen::<usize>() % (count - 1);
+				vec![if to >= from { to + 1 } else { to }]
+			},
+		}
+	}
+
+	fn record_stats(&mut self) {
+		let now = Instant::now();
+		self.wall_time += now.duration_since(self.last_mark).as_secs_f64();
+		self.last_mark = now;
+
+		let fitness: Vec<f64> = self.islands.iter().flat_map(|island| island.pop_fitness.iter().copied()).collect();
+		let genomes: Vec<&G> = self.islands.iter().flat_map(|island| island.population.iter()).collect();
+		let best_ever = self.islands.iter().map(|island| island.best_fitness).fold(f64::NEG_INFINITY, f64::max);
+		let stats = GenerationStats::new(
+			self.generation,
+			&fitness,
+			best_ever,
+			diversity(&genomes, &self.context),
+			self.islands.iter().map(|island| island.evaluations).sum(),
+			self.wall_time,
+		);
+		self.stats.push(stats);
+	}
+}
+
+impl<G: Genome> Population<G> {
+	// call after calc_fitness, the migrants keep the fitness they scored here
+	fn emigrants(&mut self, policy: MigrantPolicy, count: usize) -> Vec<(G, f64)> {
+		let count = count.min(self.pop_size);
+		let picks: Vec<usize> = match policy {
+			MigrantPolicy::Best => {
+				let mut ranked: Vec<usize> = (0..self.pop_size).collect();
+				ranked.sort_by(|&a, &b| self.pop_fitness[b].partial_cmp(&self.pop_fitness[a]).unwrap_or(Ordering::Equal));
+				ranked.truncate(count);
+				ranked
+			},
+			MigrantPolicy::Random => (0..count).map(|_| self.rng.gen::<usize>() % self.pop_size).collect(),
+			MigrantPolicy::Select(selection) => selection.select(&self.pop_fitness, count, &mut self.rng),
+		};
+		picks.iter().map(|&i| (self.population[i].clone(), self.pop_fitness[i])).collect()
+	}
+
+	// migrants take the place of the least fit genomes, so they can be
+	// picked as parents of the next generation
+	fn immigrate(&mut self, migrants: Vec<(G, f64)>) {
+		let mut ranked: Vec<usize> = (0..self.pop_size).collect();
+		ranked.sort_by(|&a, &b| self.pop_fitness[a].partial_cmp(&self.pop_fitness[b]).unwrap_or(Ordering::Equal));
+		for (&i, (genome, fitness)) in ranked.iter().zip(migrants) {
+			self.population[i] = genome;
+			self.pop_fitness[i] = fitness;
+		}
+	}
+}
@@ -0,0 +1,57 @@
+use rand::{RngCore, Error};
+use serde::{Serialize, Deserialize};
+
+// xoshiro256**, small, fast and with a state that can be saved along with
+// the rest of a run so it continues exactly where it stopped
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SeededRng {
+	state: [u64; 4],
+}
+
+impl SeededRng {
+	// the seed is spread over the state with splitmix64, which never
+	// leaves it all zero
+	pub fn new(seed: u64) -> SeededRng {
+		let mut x = seed;
+		let mut state = [0; 4];
+		for word in state.iter_mut() {
+			x = x.wrapping_add(0x9e3779b97f4a7c15);
+			let mut z = x;
+			z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+			z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+			*word = z ^ (z >> 31);
+		}
+		SeededRng { state }
+	}
+}
+
+impl RngCore for SeededRng {
+	fn next_u32(&mut self) -> u32 {
+		(self.next_u64() >> 32) as u32
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		let s = &mut self.state;
+		let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+		let t = s[1] << 17;
+		s[2] ^= s[0];
+		s[3] ^= s[1];
+		s[1] ^= s[2];
+		s[0] ^= s[3];
+		s[2] ^= t;
+		s[3] = s[3].rotate_left(45);
+		result
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		for chunk in dest.chunks_mut(8) {
+			let bytes = self.next_u64().to_le_bytes();
+			chunk.copy_from_slice(&bytes[..chunk.len()]);
+		}
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+		self.fill_bytes(dest);
+		Ok(())
+	}
+}
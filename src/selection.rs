@@ -0,0 +1,280 @@
+use rand::Rng;
+use rand::seq::{index, SliceRandom};
+use serde::{Serialize, Deserialize};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Selection {
+	// fitness proportionate, the chance of a pick is its share of the
+	// total fitness
+	#[default]
+	Roulette,
+	// the fittest of this many distinct uniformly drawn candidates, or of
+	// all of them when there are fewer
+	Tournament(usize),
+	// linear ranking with a selection pressure between 1 and 2, where
+	// 2 gives the worst candidate no chance at all
+	Rank(f64),
+	// fitness proportionate with evenly spaced pointers, so the number of
+	// picks never strays far from each candidate's expected share
+	StochasticUniversal,
+	// uniform among this fraction of the best candidates
+	Truncation(f64),
+	// fitness proportionate over exp(fitness / temperature), high
+	// temperatures flatten the differences. the temperature has to be
+	// above 0
+	Boltzmann(f64),
+}
+
+// what to do when inspect finds fitness the selection cannot use as is
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FitnessPolicy {
+	// refuse to select and hand the report back as an error
+	Error,
+	// NaN counts as 0, infinities as huge finite values and, for fitness
+	// proportionate selection, negative fitness as 0
+	#[default]
+	Clamp,
+	// ignore fitness and pick uniformly
+	Uniform,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct FitnessReport {
+	// NaN or infinite values
+	pub non_finite: usize,
+	// negative values, only counted for fitness proportionate selection
+	pub negative: usize,
+	// nothing has any fitness to be proportionate to
+	pub all_zero: bool,
+}
+
+impl FitnessReport {
+	pub fn is_degenerate(&self) -> bool {
+		self.non_finite > 0 || self.negative > 0 || self.all_zero
+	}
+}
+
+impl fmt::Display for FitnessReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Degenerate fitness: {} non finite, {} negative", self.non_finite, self.negative)?;
+		if self.all_zero {
+			write!(f, ", all zero")?;
+		}
+		Ok(())
+	}
+}
+
+impl Error for FitnessReport {}
+
+impl Selection {
+	// whether this selection needs fitness to be usable as probabilities
+	pub fn is_proportionate(self) -> bool {
+		matches!(self, Selection::Roulette | Selection::StochasticUniversal)
+	}
+
+	pub fn inspect(self, fitness: &[f64]) -> FitnessReport {
+		let mut report = FitnessReport {
+			non_finite: fitness.iter().filter(|f| !f.is_finite()).count(),
+			..FitnessReport::default()
+		};
+		if self.is_proportionate() {
+			report.negative = fitness.iter().filter(|&&f| f < 0.0).count();
+			report.all_zero = !fitness.is_empty() && fitness.iter().all(|&f| f <= 0.0 || f.is_nan());
+		}
+		report
+	}
+
+	// returns count positions into fitness, in random order. non finite
+	// fitness is clamped, see FitnessPolicy::Clamp
+	pub fn select<R: Rng + ?Sized>(self, fitness: &[f64], count: usize, rng: &mut R) -> Vec<usize> {
+		if fitness.is_empty() {
+			return Vec::new();
+		}
+		let clamped: Vec<f64> = fitness.iter().map(|&f| clamp(f, fitness.len())).collect();
+		let fitness = &clamped[..];
+
+		let mut picks = match self {
+			Selection::Roulette => {
+				let weights: Vec<f64> = fitness.iter().map(|f| f.max(0.0)).collect();
+				(0..count).map(|_| spin(&weights, rng)).collect()
+			},
+			Selection::Tournament(size) => {
+				let size = size.clamp(1, fitness.len());
+				(0..count).map(|_| {
+					let candidates = index::sample(rng, fitness.len(), size);
+					let mut best = candidates.index(0);
+					for candidate in candidates.iter() {
+						if fitness[candidate] > fitness[best] {
+							best = candidate;
+						}
+					}
+					best
+				}).collect()
+			},
+			Selection::Rank(pressure) => {
+				let n = fitness.len();
+				let mut weights = vec![1.0; n];
+				if n > 1 {
+					for (rank, &i) in by_fitness(fitness).iter().enumerate() {
+						weights[i] = (2.0 - pressure) + 2.0 * (pressure - 1.0) * rank as f64 / (n - 1) as f64;
+					}
+				}
+				(0..count).map(|_| spin(&weights, rng)).collect()
+			},
+			Selection::StochasticUniversal => {
+				let weights: Vec<f64> = fitness.iter().map(|f| f.max(0.0)).collect();
+				universal(&weights, count, rng)
+			},
+			Selection::Truncation(fraction) => {
+				let sorted = by_fitness(fitness);
+				let keep = ((fraction * fitness.len() as f64).ceil() as usize).clamp(1, fitness.len());
+				let best = &sorted[fitness.len() - keep..];
+				(0..count).map(|_| best[rng.gen::<usize>() % keep]).collect()
+			},
+			Selection::Boltzmann(temperature) => {
+				assert!(temperature > 0.0, "Boltzmann selection needs a temperature above 0, got {}.", temperature);
+				// shifted by the best fitness so exp cannot overflow
+				let max = fitness.iter().cloned().fold(f64::MIN, f64::max);
+				let weights: Vec<f64> = fitness.iter().map(|f| ((f - max) / temperature).exp()).collect();
+				(0..count).map(|_| spin(&weights, rng)).collect()
+			},
+		};
+
+		picks.shuffle(rng);
+		picks
+	}
+}
+
+// small enough that summing every candidate stays finite
+fn clamp(fitness: f64, candidates: usize) -> f64 {
+	let limit = f64::MAX / candidates as f64;
+	if fitness.is_nan() {
+		0.0
+	} else {
+		fitness.clamp(-limit, limit)
+	}
+}
+
+// positions sorted from the worst to the best fitness
+fn by_fitness(fitness: &[f64]) -> Vec<usize> {
+	let mut sorted: Vec<usize> = (0..fitness.len()).collect();
+	sorted.sort_by(|&a, &b| fitness[a].partial_cmp(&fitness[b]).unwrap_or(std::cmp::Ordering::Equal));
+	sorted
+}
+
+// one roulette pick, uniform when no candidate has any weight
+fn spin<R: Rng + ?Sized>(weights: &[f64], rng: &mut R) -> usize {
+	let total: f64 = weights.iter().sum();
+	if total <= 0.0 || !total.is_finite() {
+		return rng.gen::<usize>() % weights.len();
+	}
+
+	let mut target = rng.gen::<f64>() * total;
+	for (i, &weight) in weights.iter().enumerate() {
+		if target < weight {
+			return i;
+		}
+		target -= weight;
+	}
+	weights.len() - 1
+}
+
+fn universal<R: Rng + ?Sized>(weights: &[f64], count: usize, rng: &mut R) -> Vec<usize> {
+	let total: f64 = weights.iter().sum();
+	if total <= 0.0 || !total.is_finite() {
+		return (0..count).map(|_| rng.gen::<usize>() % weights.len()).collect();
+	}
+
+	let step = total / count as f64;
+	let mut pointer = rng.gen::<f64>() * step;
+	let mut picks = Vec::with_capacity(count);
+	let mut i = 0;
+	let mut reached = weights[0];
+	for _pick in 0..count {
+		while pointer >= reached && i + 1 < weights.len() {
+			i += 1;
+			reached += weights[i];
+		}
+		picks.push(i);
+		pointer += step;
+	}
+	picks
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use crate::rng::SeededRng;
+
+	pub(crate) const STRATEGIES: [Selection; 6] = [
+		Selection::Roulette,
+		Selection::Tournament(3),
+		Selection::Rank(1.5),
+		Selection::StochasticUniversal,
+		Selection::Truncation(0.3),
+		Selection::Boltzmann(1.0),
+	];
+
+	// all zero, all negative, with NaN and with infinities
+	pub(crate) fn degenerate_fitness(len: usize) -> Vec<Vec<f64>> {
+		let mut nan: Vec<f64> = (0..len).map(|i| i as f64).collect();
+		nan[len / 2] = f64::NAN;
+		let mut infinite: Vec<f64> = (0..len).map(|i| i as f64).collect();
+		infinite[0] = f64::INFINITY;
+		infinite[len - 1] = f64::NEG_INFINITY;
+		vec![vec![0.0; len], (0..len).map(|i| -1.0 - i as f64).collect(), nan, infinite]
+	}
+
+	#[test]
+	fn degenerate_fitness_still_gives_valid_picks() {
+		let mut rng = SeededRng::new(1);
+		for fitness in degenerate_fitness(10).iter() {
+			for &selection in STRATEGIES.iter() {
+				let picks = selection.select(fitness, 25, &mut rng);
+				assert_eq!(picks.len(), 25);
+				assert!(picks.iter().all(|&i| i < fitness.len()), "{:?} picked outside {:?}", selection, fitness);
+			}
+			assert!(Selection::Roulette.inspect(fitness).is_degenerate(), "{:?}", fitness);
+		}
+	}
+
+	#[test]
+	fn truncation_only_picks_the_best() {
+		let fitness: Vec<f64> = (0..10).map(|i| i as f64).collect();
+		let picks = Selection::Truncation(0.3).select(&fitness, 100, &mut SeededRng::new(2));
+		assert!(picks.iter().all(|&i| i >= 7), "{:?}", picks);
+	}
+
+	#[test]
+	fn stochastic_universal_stays_within_one_of_the_share() {
+		let fitness = [1.0, 2.0, 3.0, 4.0, 0.5, 1.5];
+		let total: f64 = fitness.iter().sum();
+		for seed in 0..20 {
+			let picks = Selection::StochasticUniversal.select(&fitness, 24, &mut SeededRng::new(seed));
+			for (i, f) in fitness.iter().enumerate() {
+				let expected = f / total * 24.0;
+				let count = picks.iter().filter(|&&pick| pick == i).count() as f64;
+				assert!((count - expected).abs() <= 1.0, "candidate {} picked {} times, expected {}", i, count, expected);
+			}
+		}
+	}
+
+	#[test]
+	fn tournament_of_everyone_picks_the_best() {
+		let fitness = [3.0, 9.0, 1.0, 4.0, 2.0];
+		for &size in [5, 8].iter() {
+			let picks = Selection::Tournament(size).select(&fitness, 50, &mut SeededRng::new(4));
+			assert!(picks.iter().all(|&i| i == 1), "{:?}", picks);
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "temperature above 0")]
+	fn boltzmann_rejects_a_zero_temperature() {
+		Selection::Boltzmann(0.0).select(&[1.0, 2.0], 1, &mut SeededRng::new(1));
+	}
+}
@@ -3,7 +3,11 @@ use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::rect::Rect;
 use std::time::{Duration, Instant};
-use rand;
+use rand::Rng;
+
+use crate::nn::NN;
+use crate::rng::SeededRng;
+use crate::task::{Task, MultiObjectiveTask};
 
 pub enum SnakeDirection {
         None,
@@ -27,10 +31,17 @@ pub struct Snake {
         has_won: bool,
         has_died: bool,
         score: usize,
+        // places the food
+        rng: SeededRng,
 }
 
 impl Snake {
         pub fn new() -> Snake {
+                Snake::with_seed(rand::random())
+        }
+
+        // the same seed and moves always play out the same game
+        pub fn with_seed(seed: u64) -> Snake {
                 let mut snake = Snake {
                         grid: [[' '; 10]; 10],
                         direction: SnakeDirection::None,
@@ -39,6 +50,7 @@ impl Snake {
                         has_won: false,
                         has_died: false,
                         score: 3,
+                        rng: SeededRng::new(seed),
                 };
 
                 snake.snake.push(Position { x: 4, y: 4, });
@@ -67,6 +79,27 @@ impl Snake {
                 self.has_won || self.has_died
         }
 
+        pub fn has_won(&self) -> bool {
+                self.has_won
+        }
+
+        pub fn get_score(&self) -> usize {
+                self.score
+        }
+
+        // x and y of the snake's head
+        pub fn get_head(&self) -> (usize, usize) {
+                (self.snake[0].x, self.snake[0].y)
+        }
+
+        pub fn get_food(&self) -> (usize, usize) {
+                (self.food.x, self.food.y)
+        }
+
+        pub fn get_cell(&self, x: usize, y: usize) -> char {
+                self.grid[y][x]
+        }
+
         pub fn print_grid(&self) {
                 for i in 0..10 {
                         for j in 0..10 {
@@ -107,8 +140,8 @@ impl Snake {
 
                                 if self.score < 64 {
                                         loop {
-                                                let food_x = rand::random::<usize>() % 8 + 1;
-                                                let food_y = rand::random::<usize>() % 8 + 1;
+                                                let food_x = self.rng.gen::<usize>() % 8 + 1;
+                                                let food_y = self.rng.gen::<usize>() % 8 + 1;
         
                                                 if self.grid[food_y][food_x] == ' ' {
                                                         self.food = Position { x: food_x, y: food_y };
@@ -202,4 +235,109 @@ impl Snake {
                 }
                 println!("Score: {}", self.score);
         }
-}
\ No newline at end of file
+}
+
+// evolves networks that steer the snake. the inputs are whether the cell
+// left, right, above and below the head is deadly, then the x and y
+// distance to the food, the outputs are up, down, left and right
+pub struct SnakeTask {
+        // an episode ends after this many moves without eating
+        pub max_idle_moves: usize,
+}
+
+impl SnakeTask {
+        pub fn new(max_idle_moves: usize) -> SnakeTask {
+                SnakeTask { max_idle_moves }
+        }
+}
+
+impl Task<NN> for SnakeTask {
+        // 100 per apple plus up to 99 for surviving, so staying alive helps
+        // early on but never counts as much as an apple
+        fn evaluate<R: Rng + ?Sized>(&self, nn: &NN, rng: &mut R) -> f64 {
+                let (apples, moves) = self.episode(nn, rng);
+                (apples * 100 + moves.min(99)) as f64
+        }
+
+        // every free cell filled
+        fn is_solved(&self, fitness: f64) -> bool {
+                fitness >= 6100.0
+        }
+}
+
+impl MultiObjectiveTask<NN> for SnakeTask {
+        // apples eaten and moves survived
+        fn evaluate_objectives<R: Rng + ?Sized>(&self, nn: &NN, rng: &mut R) -> Vec<f64> {
+                let (apples, moves) = self.episode(nn, rng);
+                vec![apples as f64, moves as f64]
+        }
+}
+
+impl SnakeTask {
+        // plays until the snake dies or starves, returns the apples eaten
+        // and the moves made
+        fn episode<R: Rng + ?Sized>(&self, nn: &NN, rng: &mut R) -> (usize, usize) {
+                // an episode runs the network for hundreds of moves, so a
+                // strictly layered one is worth compiling first. the rest
+                // fall back to evaluate
+                let mut dense = nn.compile().ok();
+                let mut scratch = nn.new_scratch();
+                let mut snake = Snake::with_seed(rng.gen());
+                let mut moves = 0;
+                let mut idle_moves = 0;
+                while !snake.is_game_over() && idle_moves < self.max_idle_moves {
+                        let inputs = snake_inputs(&snake);
+                        let outputs = match dense.as_mut() {
+                                Some(dense) => dense.feed_forward(&inputs),
+                                None => nn.evaluate(&inputs, &mut scratch),
+                        }.unwrap();
+                        snake.set_direction(snake_direction(outputs));
+
+                        let score = snake.get_score();
+                        snake.move_snake();
+                        moves += 1;
+                        if snake.get_score() > score {
+                                idle_moves = 0;
+                        } else {
+                                idle_moves += 1;
+                        }
+                }
+
+                (snake.get_score() - 3, moves)
+        }
+}
+
+fn snake_inputs(snake: &Snake) -> [f64; 6] {
+        let (x, y) = snake.get_head();
+        let (food_x, food_y) = snake.get_food();
+        let deadly = |x: usize, y: usize| {
+                let cell = snake.get_cell(x, y);
+                (cell == 'W' || cell == 'S') as i64 as f64
+        };
+
+        [
+                deadly(x - 1, y),
+                deadly(x + 1, y),
+                deadly(x, y - 1),
+                deadly(x, y + 1),
+                food_x as f64 - x as f64,
+                food_y as f64 - y as f64,
+        ]
+}
+
+// picks the direction of the strongest output
+fn snake_direction(outputs: &[f64]) -> SnakeDirection {
+        let mut strongest = 0;
+        for i in 1..outputs.len() {
+                if outputs[i] > outputs[strongest] {
+                        strongest = i;
+                }
+        }
+
+        match strongest {
+                0 => SnakeDirection::Up,
+                1 => SnakeDirection::Down,
+                2 => SnakeDirection::Left,
+                _ => SnakeDirection::Right,
+        }
+}
@@ -0,0 +1,86 @@
+use crate::genome::Genome;
+use crate::population::float;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+// a group of structurally similar genomes that mostly compete with each
+// other, which gives new topologies a few generations to tune their weights
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Species<G: Genome> {
+	id: usize,
+	representative: G,
+	members: Vec<usize>,
+	#[serde(with = "float")]
+	best_fitness: f64,
+	stagnant_generations: usize,
+}
+
+impl<G: Genome> Species<G> {
+	pub fn new(id: usize, representative: G) -> Species<G> {
+		Species {
+			id,
+			representative,
+			members: Vec::new(),
+			best_fitness: f64::NEG_INFINITY,
+			stagnant_generations: 0,
+		}
+	}
+
+	pub fn get_id(&self) -> usize {
+		self.id
+	}
+
+	pub fn get_size(&self) -> usize {
+		self.members.len()
+	}
+
+	pub fn get_members(&self) -> &[usize] {
+		&self.members
+	}
+
+	pub fn get_representative(&self) -> &G {
+		&self.representative
+	}
+
+	pub fn get_best_fitness(&self) -> f64 {
+		self.best_fitness
+	}
+
+	pub fn get_stagnant_generations(&self) -> usize {
+		self.stagnant_generations
+	}
+
+	pub fn add_member(&mut self, index: usize) {
+		self.members.push(index);
+	}
+
+	pub fn clear_members(&mut self) {
+		self.members.clear();
+	}
+
+	// sum of the members' shared fitness, i.e. each member's fitness divided
+	// by the size of the species
+	pub fn get_shared_fitness(&self, pop_fitness: &[f64]) -> f64 {
+		let size = self.members.len() as f64;
+		self.members.iter().map(|&i| pop_fitness[i] / size).sum()
+	}
+
+	// called once the members of a generation are assigned, tracks
+	// stagnation and picks the representative for the next generation
+	pub fn update<R: Rng + ?Sized>(&mut self, population: &[G], pop_fitness: &[f64], rng: &mut R) {
+		if self.members.is_empty() {
+			return;
+		}
+
+		let best = self.members.iter().map(|&i| pop_fitness[i]).fold(f64::NEG_INFINITY, f64::max);
+		if best > self.best_fitness {
+			self.best_fitness = best;
+			self.stagnant_generations = 0;
+		} else {
+			self.stagnant_generations += 1;
+		}
+
+		let representative = self.members[rng.gen::<usize>() % self.members.len()];
+		self.representative = population[representative].clone();
+	}
+}
@@ -0,0 +1,84 @@
+use serde::{Serialize, Deserialize};
+use std::fmt::Write;
+
+use crate::population::float;
+
+// summary of one generation's fitness, recorded right after evaluation
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GenerationStats {
+	pub generation: usize,
+	#[serde(with = "float")]
+	pub min: f64,
+	#[serde(with = "float")]
+	pub max: f64,
+	#[serde(with = "float")]
+	pub mean: f64,
+	#[serde(with = "float")]
+	pub median: f64,
+	#[serde(with = "float")]
+	pub std_dev: f64,
+	// best fitness of the whole run so far
+	#[serde(with = "float")]
+	pub best_ever: f64,
+	// mean genome distance between pairs of the population
+	#[serde(with = "float")]
+	pub diversity: f64,
+	// genomes evaluated since the run started
+	pub evaluations: usize,
+	// seconds since the run started, not counting time between a
+	// checkpoint and resuming from it
+	pub wall_time: f64,
+}
+
+impl GenerationStats {
+	pub fn new(generation: usize, fitness: &[f64], best_ever: f64, diversity: f64, evaluations: usize, wall_time: f64) -> GenerationStats {
+		let n = fitness.len().max(1) as f64;
+		let mean = fitness.iter().sum::<f64>() / n;
+		let variance = fitness.iter().map(|f| (f - mean) * (f - mean)).sum::<f64>() / n;
+
+		let mut sorted = fitness.to_vec();
+		sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+		let median = match sorted.len() {
+			0 => 0.0,
+			len if len % 2 == 1 => sorted[len / 2],
+			len => (sorted[len / 2 - 1] + sorted[len / 2]) / 2.0,
+		};
+
+		GenerationStats {
+			generation,
+			min: sorted.first().copied().unwrap_or(0.0),
+			max: sorted.last().copied().unwrap_or(0.0),
+			mean,
+			median,
+			std_dev: variance.sqrt(),
+			best_ever,
+			diversity,
+			evaluations,
+			wall_time,
+		}
+	}
+}
+
+const CSV_HEADER: &str = "generation,min,max,mean,median,std_dev,best_ever,diversity,evaluations,wall_time";
+
+pub fn to_csv(stats: &[GenerationStats]) -> String {
+	let mut csv = String::new();
+	writeln!(csv, "{}", CSV_HEADER).unwrap();
+	for s in stats.iter() {
+		writeln!(
+			csv,
+			"{},{},{},{},{},{},{},{},{},{}",
+			s.generation, s.min, s.max, s.mean, s.median, s.std_dev, s.best_ever, s.diversity, s.evaluations, s.wall_time
+		).unwrap();
+	}
+	csv
+}
+
+// one JSON object per line, non-finite values are written as in checkpoints
+pub fn to_jsonl(stats: &[GenerationStats]) -> String {
+	let mut jsonl = String::new();
+	for s in stats.iter() {
+		writeln!(jsonl, "{}", serde_json::to_string(s).expect("stats always serialize")).unwrap();
+	}
+	jsonl
+}
@@ -0,0 +1,20 @@
+use rand::Rng;
+
+// a problem Population can evolve genomes for
+pub trait Task<G> {
+	// plays one episode with the genome, higher fitness is better. any
+	// randomness in the episode has to come from rng
+	fn evaluate<R: Rng + ?Sized>(&self, genome: &G, rng: &mut R) -> f64;
+
+	// whether a genome with this fitness solved the task
+	fn is_solved(&self, _fitness: f64) -> bool {
+		false
+	}
+}
+
+// a problem scored on several objectives at once, see nsga2
+pub trait MultiObjectiveTask<G> {
+	// plays one episode with the genome and returns one value per
+	// objective, always in the same order and each one higher is better
+	fn evaluate_objectives<R: Rng + ?Sized>(&self, genome: &G, rng: &mut R) -> Vec<f64>;
+}
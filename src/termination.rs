@@ -0,0 +1,153 @@
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+use crate::stats::GenerationStats;
+
+// when a run should stop, checked after every evaluated generation
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Termination {
+	// this many generations have been evaluated
+	MaxGenerations(usize),
+	// the best fitness of the run reached this value
+	TargetFitness(f64),
+	// the best fitness of the run has not improved for this many generations
+	Stagnation(usize),
+	// the run has taken this many seconds
+	WallTime(f64),
+	// this many genomes have been evaluated
+	MaxEvaluations(usize),
+	// the task says a genome solved it
+	Solved,
+	// stops as soon as one of these is met
+	Any(Vec<Termination>),
+	// stops once all of these are met at the same time, empty never stops
+	All(Vec<Termination>),
+}
+
+impl Default for Termination {
+	fn default() -> Termination {
+		Termination::MaxGenerations(100)
+	}
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum StopReason {
+	MaxGenerations(usize),
+	TargetFitness(f64),
+	Stagnation(usize),
+	WallTime(f64),
+	MaxEvaluations(usize),
+	Solved,
+	// every criterion of an All
+	All(Vec<StopReason>),
+}
+
+impl fmt::Display for StopReason {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			StopReason::MaxGenerations(generations) => write!(f, "Evaluated {} generations", generations),
+			StopReason::TargetFitness(target) => write!(f, "Reached the target fitness {}", target),
+			StopReason::Stagnation(generations) => write!(f, "Best fitness did not improve for {} generations", generations),
+			StopReason::WallTime(seconds) => write!(f, "Ran out of the {} second time budget", seconds),
+			StopReason::MaxEvaluations(evaluations) => write!(f, "Evaluated {} genomes", evaluations),
+			StopReason::Solved => write!(f, "Solved the task"),
+			StopReason::All(reasons) => {
+				for (i, reason) in reasons.iter().enumerate() {
+					if i > 0 {
+						write!(f, "; ")?;
+					}
+					write!(f, "{}", reason)?;
+				}
+				Ok(())
+			},
+		}
+	}
+}
+
+impl Termination {
+	// stats holds one entry per evaluated generation, the last one being
+	// the generation that was just evaluated
+	pub fn check(&self, stats: &[GenerationStats], solved: bool) -> Option<StopReason> {
+		let last = stats.last()?;
+		match self {
+			Termination::MaxGenerations(generations) => {
+				(stats.len() >= *generations).then_some(StopReason::MaxGenerations(*generations))
+			},
+			Termination::TargetFitness(target) => {
+				(last.best_ever >= *target).then_some(StopReason::TargetFitness(*target))
+			},
+			Termination::Stagnation(generations) => {
+				let stagnant = stats.len() > *generations && last.best_ever <= stats[stats.len() - 1 - generations].best_ever;
+				stagnant.then_some(StopReason::Stagnation(*generations))
+			},
+			Termination::WallTime(seconds) => {
+				(last.wall_time >= *seconds).then_some(StopReason::WallTime(*seconds))
+			},
+			Termination::MaxEvaluations(evaluations) => {
+				(last.evaluations >= *evaluations).then_some(StopReason::MaxEvaluations(*evaluations))
+			},
+			Termination::Solved => solved.then_some(StopReason::Solved),
+			Termination::Any(criteria) => criteria.iter().find_map(|c| c.check(stats, solved)),
+			Termination::All(criteria) => {
+				if criteria.is_empty() {
+					return None;
+				}
+				let reasons: Option<Vec<StopReason>> = criteria.iter().map(|c| c.check(stats, solved)).collect();
+				reasons.map(StopReason::All)
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use Termination::*;
+
+	// one generation per best_ever value, each evaluating 10 genomes and
+	// taking a second
+	fn stats(best_ever: &[f64]) -> Vec<GenerationStats> {
+		best_ever.iter().enumerate().map(|(i, &best)| {
+			GenerationStats::new(i, &[best], best, 0.0, (i + 1) * 10, (i + 1) as f64)
+		}).collect()
+	}
+
+	#[test]
+	fn check_every_criterion() {
+		let both = All(vec![MaxGenerations(2), TargetFitness(3.0)]);
+		let nested = Any(vec![All(vec![MaxGenerations(2), Solved]), TargetFitness(100.0)]);
+		let table: Vec<(Termination, Vec<f64>, bool, Option<StopReason>)> = vec![
+			(MaxGenerations(1), vec![], true, None),
+			(MaxGenerations(3), vec![1.0, 1.0], false, None),
+			(MaxGenerations(3), vec![1.0, 1.0, 1.0], false, Some(StopReason::MaxGenerations(3))),
+			(TargetFitness(5.0), vec![4.9], false, None),
+			(TargetFitness(5.0), vec![4.9, 5.0], false, Some(StopReason::TargetFitness(5.0))),
+			// no improvement over the last 2 generations needs 3 of them
+			(Stagnation(2), vec![1.0, 1.0], false, None),
+			(Stagnation(2), vec![1.0, 1.0, 1.0], false, Some(StopReason::Stagnation(2))),
+			(Stagnation(2), vec![0.0, 1.0, 2.0, 2.0], false, None),
+			(Stagnation(2), vec![0.0, 1.0, 2.0, 2.0, 2.0], false, Some(StopReason::Stagnation(2))),
+			(WallTime(3.0), vec![1.0, 1.0], false, None),
+			(WallTime(3.0), vec![1.0, 1.0, 1.0], false, Some(StopReason::WallTime(3.0))),
+			(MaxEvaluations(30), vec![1.0, 1.0], false, None),
+			(MaxEvaluations(30), vec![1.0, 1.0, 1.0], false, Some(StopReason::MaxEvaluations(30))),
+			(Solved, vec![1.0], false, None),
+			(Solved, vec![1.0], true, Some(StopReason::Solved)),
+			(Any(vec![]), vec![1.0], true, None),
+			(All(vec![]), vec![1.0], true, None),
+			(Any(vec![MaxGenerations(5), Solved]), vec![1.0], true, Some(StopReason::Solved)),
+			(Any(vec![MaxGenerations(5), Solved]), vec![1.0], false, None),
+			(both.clone(), vec![1.0, 2.0], false, None),
+			(both.clone(), vec![3.0], false, None),
+			(both, vec![1.0, 3.0], false, Some(StopReason::All(vec![StopReason::MaxGenerations(2), StopReason::TargetFitness(3.0)]))),
+			(nested.clone(), vec![1.0, 1.0], false, None),
+			(nested.clone(), vec![1.0, 1.0], true, Some(StopReason::All(vec![StopReason::MaxGenerations(2), StopReason::Solved]))),
+			(nested, vec![100.0], false, Some(StopReason::TargetFitness(100.0))),
+		];
+
+		for (termination, best_ever, solved, expected) in table {
+			assert_eq!(termination.check(&stats(&best_ever), solved), expected, "{:?} on {:?}", termination, best_ever);
+		}
+	}
+}